@@ -0,0 +1,50 @@
+/* Exercises the C API against the release build of the library. Run with `make -C ffi test`. */
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "aoc.h"
+
+static const char DAY01_EXAMPLE[] =
+    "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+
+static void expect_answer(uint32_t day, uint32_t part, const char *input, const char *expected) {
+    char *out = NULL;
+    int32_t status = aoc_solve(day, part, (const uint8_t *)input, strlen(input), &out);
+    if (status != AOC_OK || strcmp(out, expected) != 0) {
+        fprintf(stderr, "day %u part %u: status %d, got '%s', expected '%s'\n", day, part, status,
+                out, expected);
+        aoc_free_string(out);
+        assert(0);
+    }
+    aoc_free_string(out);
+}
+
+static void expect_status(uint32_t day, uint32_t part, const char *input, int32_t expected) {
+    char *out = NULL;
+    int32_t status = aoc_solve(day, part, (const uint8_t *)input, strlen(input), &out);
+    assert(status == expected);
+    assert(out != NULL);
+    aoc_free_string(out);
+}
+
+int main(void) {
+    size_t days = aoc_day_count();
+    assert(days >= 12);
+    printf("%zu days available\n", days);
+
+    expect_answer(1, 1, DAY01_EXAMPLE, "24000");
+    expect_answer(1, 2, DAY01_EXAMPLE, "45000");
+    expect_answer(6, 1, "mjqjpqmgbljsphdztnvjfqwrcgsmlb", "7");
+
+    expect_status(0, 1, "", AOC_INVALID_ARGUMENT);
+    expect_status((uint32_t)days + 1, 1, "", AOC_INVALID_ARGUMENT);
+    expect_status(1, 3, "", AOC_INVALID_ARGUMENT);
+    expect_status(1, 1, "not a number", AOC_FAILED);
+
+    aoc_free_string(NULL);
+    assert(aoc_solve(1, 1, NULL, 0, NULL) == AOC_INVALID_ARGUMENT);
+
+    printf("All FFI checks passed\n");
+    return 0;
+}
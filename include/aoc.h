@@ -0,0 +1,41 @@
+#ifndef AOC_H
+#define AOC_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define AOC_OK 0
+
+#define AOC_INVALID_ARGUMENT 1
+
+#define AOC_FAILED 2
+
+// Number of available days. Valid days are `1..=aoc_day_count()`.
+size_t aoc_day_count(void);
+
+// Solves `part` (1 or 2) of `day` for the `input_len` bytes at `input`.
+//
+// Returns `AOC_OK` and stores the answer in `*out`, or another status code and
+// stores an error message in `*out`. Either string must be released with
+// `aoc_free_string`.
+//
+// # Safety
+//
+// `input` must point to `input_len` readable bytes (it may be null if
+// `input_len` is 0) and `out` must be a valid pointer to writable storage.
+int32_t aoc_solve(uint32_t day, uint32_t part, const uint8_t *input, size_t input_len, char **out);
+
+// Releases a string returned by `aoc_solve`. Passing null is a no-op.
+//
+// # Safety
+//
+// `string` must be null or a pointer obtained from `aoc_solve` that has not
+// been freed yet.
+void aoc_free_string(char *string);
+
+#endif  /* AOC_H */
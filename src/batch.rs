@@ -0,0 +1,274 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::output::{json_fields, OutputFormat};
+use crate::runner::{self, RunOptions, Status, Task, TaskResult};
+use crate::solution::Solution;
+use crate::util::json_string;
+
+/// The results of running one day against one input file.
+pub struct BatchEntry {
+    pub path: PathBuf,
+    pub results: Vec<TaskResult>,
+    pub expected: Vec<Option<String>>,
+}
+
+impl BatchEntry {
+    /// `None` when there is no expected answer to compare against.
+    pub fn matches(&self, idx: usize) -> Option<bool> {
+        let expected = self.expected[idx].as_ref()?;
+        match &self.results[idx].status {
+            Status::Solved(answer) => Some(answer.trim() == expected.trim()),
+            _ => Some(false),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        (0..self.results.len())
+            .all(|idx| self.results[idx].is_solved() && self.matches(idx) != Some(false))
+    }
+
+    fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Input files for `day` in `dir`, i.e. everything named `dayNN*.txt`, sorted by name.
+pub fn find_inputs(dir: &Path, day: usize) -> io::Result<Vec<PathBuf>> {
+    let prefix = format!("day{:02}", day);
+    let mut inputs: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with(&prefix) && name.ends_with(".txt")
+        })
+        .collect();
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// Expected answers live next to the input: `day01-alice.txt` is checked against
+/// `day01-alice.part1` and `day01-alice.part2` (or `day01-alice.<mode>`) when
+/// those exist.
+fn expected_answer(path: &Path, task: &Task) -> Option<String> {
+    std::fs::read_to_string(path.with_extension(task.name())).ok()
+}
+
+pub fn run_batch(
+    solution: &Arc<dyn Solution>,
+    day: usize,
+    dir: &Path,
+    tasks: &[Task],
+    options: &RunOptions,
+) -> io::Result<Vec<BatchEntry>> {
+    let entries = find_inputs(dir, day)?
+        .into_iter()
+        .map(|path| {
+            let results = runner::run_file(solution, day, &path, tasks, options);
+            let expected = tasks
+                .iter()
+                .map(|task| expected_answer(&path, task))
+                .collect();
+            BatchEntry {
+                path,
+                results,
+                expected,
+            }
+        })
+        .collect();
+    Ok(entries)
+}
+
+pub fn render(format: OutputFormat, day: usize, entries: &[BatchEntry]) -> String {
+    match format {
+        OutputFormat::Text => render_text(day, entries),
+        OutputFormat::Json => render_json(entries),
+    }
+}
+
+fn cell(entry: &BatchEntry, idx: usize) -> String {
+    let value = match &entry.results[idx].status {
+        Status::Solved(answer) => answer.replace('\n', "\\n"),
+        Status::Failed(error) => format!("error: {}", error),
+        Status::TimedOut(timeout) => format!("timed out after {:?}", timeout),
+    };
+    match entry.matches(idx) {
+        Some(true) => format!("{} ✓", value),
+        Some(false) => format!(
+            "{} ✗ (expected {})",
+            value,
+            entry.expected[idx].as_deref().unwrap_or("").trim()
+        ),
+        None => value,
+    }
+}
+
+fn render_text(day: usize, entries: &[BatchEntry]) -> String {
+    if entries.is_empty() {
+        return format!("Day {:02}\nNo inputs found", day);
+    }
+    let mut header = vec!["File".to_owned()];
+    header.extend(
+        entries[0]
+            .results
+            .iter()
+            .map(|result| result.task.to_string()),
+    );
+    let mut rows = vec![header];
+    for entry in entries {
+        let mut row = vec![entry.file_name()];
+        row.extend((0..entry.results.len()).map(|idx| cell(entry, idx)));
+        rows.push(row);
+    }
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|col| {
+            rows.iter()
+                .map(|row| row[col].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut lines = vec![format!("Day {:02}", day)];
+    for row in rows.iter() {
+        let padded: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(value, width)| {
+                let padding = width - value.chars().count();
+                format!("{}{}", value, " ".repeat(padding))
+            })
+            .collect();
+        lines.push(padded.join("  ").trim_end().to_owned());
+    }
+    lines.join("\n")
+}
+
+fn render_json(entries: &[BatchEntry]) -> String {
+    let mut lines = Vec::new();
+    for entry in entries {
+        for (idx, result) in entry.results.iter().enumerate() {
+            let mut fields = format!(
+                "\"file\":{},{}",
+                json_string(&entry.path.to_string_lossy()),
+                json_fields(result)
+            );
+            if let Some(expected) = &entry.expected[idx] {
+                fields.push_str(&format!(
+                    ",\"expected\":{},\"matches\":{}",
+                    json_string(expected.trim()),
+                    entry.matches(idx) == Some(true)
+                ));
+            }
+            lines.push(format!("{{{}}}", fields));
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY01_EXAMPLE: &str =
+        "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+
+    /// A fresh, empty directory under the system temp directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-batch-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(dir: &Path, name: &str, contents: &str) {
+        std::fs::write(dir.join(name), contents).unwrap();
+    }
+
+    #[test]
+    fn finds_inputs_for_the_day_only() {
+        let dir = temp_dir("find");
+        for name in [
+            "day01.txt",
+            "day01-bob.txt",
+            "day01-alice.txt",
+            "day01-alice.part1",
+            "day02.txt",
+            "day1.txt",
+            "notes.txt",
+            "day01.md",
+        ] {
+            write(&dir, name, "");
+        }
+        std::fs::create_dir(dir.join("day01-dir.txt")).unwrap();
+        let names: Vec<String> = find_inputs(&dir, 1)
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["day01-alice.txt", "day01-bob.txt", "day01.txt"]);
+        assert!(find_inputs(&dir, 3).unwrap().is_empty());
+        assert!(find_inputs(&dir.join("missing"), 1).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compares_answers_with_sidecar_files() {
+        let dir = temp_dir("sidecars");
+        // Correct part 1 (whitespace is ignored), wrong part 2.
+        write(&dir, "day01-alice.txt", DAY01_EXAMPLE);
+        write(&dir, "day01-alice.part1", "24000\n");
+        write(&dir, "day01-alice.part2", "1");
+        // No expected answers at all.
+        write(&dir, "day01-bob.txt", DAY01_EXAMPLE);
+        // Both answers right.
+        write(&dir, "day01-carol.txt", DAY01_EXAMPLE);
+        write(&dir, "day01-carol.part1", "24000");
+        write(&dir, "day01-carol.part2", "45000");
+        // Unparseable input with a matching-looking answer.
+        write(&dir, "day01-dave.txt", "x\n");
+        write(&dir, "day01-dave.part1", "x");
+
+        let solution = crate::registry().remove(0);
+        let entries =
+            run_batch(&solution, 1, &dir, &Task::parts(), &RunOptions::default()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let summary: Vec<(String, Option<bool>, Option<bool>, bool)> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.file_name(),
+                    entry.matches(0),
+                    entry.matches(1),
+                    entry.is_ok(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("day01-alice.txt".to_owned(), Some(true), Some(false), false),
+                ("day01-bob.txt".to_owned(), None, None, true),
+                ("day01-carol.txt".to_owned(), Some(true), Some(true), true),
+                ("day01-dave.txt".to_owned(), Some(false), None, false),
+            ]
+        );
+        assert!(render(OutputFormat::Text, 1, &entries[..1])
+            .contains("day01-alice.txt  24000 ✓  45000 ✗ (expected 1)"));
+    }
+
+    #[test]
+    fn renders_missing_inputs() {
+        assert_eq!(
+            render(OutputFormat::Text, 4, &[]),
+            "Day 04\nNo inputs found"
+        );
+        assert_eq!(render(OutputFormat::Json, 4, &[]), "");
+    }
+}
@@ -0,0 +1,207 @@
+//! Optional `aoc.toml` with defaults for the command line, e.g.
+//!
+//! ```toml
+//! data_dir = "data"
+//! format = "json"
+//! days = [1, 7, 11]
+//! timeout = "10s"
+//! jobs = 4
+//!
+//! [params.day07]
+//! disk_size = 80000000
+//!
+//! [params.day11]
+//! part2_rounds = 500
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use simple_error::SimpleError;
+
+use crate::output::OutputFormat;
+use crate::solution::{Params, Result, Solution};
+
+pub const DEFAULT_CONFIG_FILE: &str = "aoc.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub data_dir: Option<PathBuf>,
+    pub format: Option<OutputFormat>,
+    pub days: Option<Vec<usize>>,
+    pub timeout: Option<String>,
+    pub jobs: Option<usize>,
+    #[serde(default)]
+    pub params: HashMap<String, HashMap<String, toml::Value>>,
+}
+
+impl Config {
+    /// Reads `path` if given, otherwise `aoc.toml` in the working directory if it
+    /// exists, otherwise returns the empty configuration.
+    pub fn load(path: Option<&Path>) -> Result<Config> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG_FILE).is_file() => Path::new(DEFAULT_CONFIG_FILE),
+            None => return Ok(Config::default()),
+        };
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| SimpleError::new(format!("{}: {}", path.display(), error)))?;
+        let config: Config = toml::from_str(&contents)
+            .map_err(|error| SimpleError::new(format!("{}: {}", path.display(), error)))?;
+        Ok(config)
+    }
+
+    /// Parameter overrides keyed by day. Tables may be named `day07`, `day7` or `7`,
+    /// and may only set the parameters that day reads.
+    pub fn day_params(&self, registry: &[Arc<dyn Solution>]) -> Result<HashMap<usize, Params>> {
+        let mut all_params = HashMap::new();
+        for (name, values) in &self.params {
+            let day = name
+                .trim_start_matches("day")
+                .parse::<usize>()
+                .map_err(|_| SimpleError::new(format!("Invalid day in [params.{}]", name)))?;
+            if day < 1 || day > registry.len() {
+                return Err(Box::new(SimpleError::new(format!(
+                    "Invalid day in [params.{}], supported values: 1..{}",
+                    name,
+                    registry.len()
+                ))));
+            }
+            let known = registry[day - 1].params();
+            let params: &mut Params = all_params.entry(day).or_default();
+            for (key, value) in values {
+                if !known.contains(&key.as_str()) {
+                    return Err(Box::new(SimpleError::new(unknown_param(
+                        key,
+                        &format!("[params.{}]", name),
+                        known,
+                    ))));
+                }
+                let value = match value {
+                    toml::Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                params.insert(key, &value);
+            }
+        }
+        Ok(all_params)
+    }
+}
+
+/// Error message for a parameter `key` that nothing in `context` reads.
+pub fn unknown_param(key: &str, context: &str, known: &[&str]) -> String {
+    if known.is_empty() {
+        format!(
+            "Unknown parameter '{}' in {}, no parameters are supported",
+            key, context
+        )
+    } else {
+        format!(
+            "Unknown parameter '{}' in {}, known parameters: {}",
+            key,
+            context,
+            known.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    /// Writes `contents` to a file of its own in the system temp directory.
+    fn write_config(name: &str, contents: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("aoc-config-{}-{}.toml", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_every_field() {
+        let path = write_config(
+            "full",
+            "data_dir = \"inputs\"\nformat = \"json\"\ndays = [1, 7]\ntimeout = \"10s\"\njobs = 4\n\
+             \n[params.day07]\ndisk_size = 80000000\n",
+        );
+        let config = Config::load(Some(&path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(config.data_dir, Some(PathBuf::from("inputs")));
+        assert!(matches!(config.format, Some(OutputFormat::Json)));
+        assert_eq!(config.days, Some(vec![1, 7]));
+        assert_eq!(config.timeout.as_deref(), Some("10s"));
+        assert_eq!(config.jobs, Some(4));
+        assert_eq!(config.params.len(), 1);
+    }
+
+    #[test]
+    fn load_errors_name_the_file() {
+        let missing = std::env::temp_dir().join("aoc-config-does-not-exist.toml");
+        let error = Config::load(Some(&missing)).unwrap_err().to_string();
+        assert!(
+            error.starts_with(&missing.display().to_string()),
+            "{}",
+            error
+        );
+
+        for (name, contents) in [("syntax", "jobs = "), ("unknown", "threads = 4\n")] {
+            let path = write_config(name, contents);
+            let error = Config::load(Some(&path)).unwrap_err().to_string();
+            std::fs::remove_file(&path).unwrap();
+            assert!(error.starts_with(&path.display().to_string()), "{}", error);
+        }
+    }
+
+    #[test]
+    fn day_params_accept_every_table_name() {
+        let config: Config = toml::from_str(
+            "[params.day07]\ndisk_size = 100\n\
+             [params.day1]\ntop_n = 5\n\
+             [params.5]\ncrane = \"9000\"\nundo = 2\n",
+        )
+        .unwrap();
+        let params = config.day_params(&registry()).unwrap();
+        assert_eq!(params.len(), 3);
+        assert_eq!(params[&7].get("disk_size", 0).unwrap(), 100);
+        assert_eq!(params[&1].get("top_n", 3).unwrap(), 5);
+        assert_eq!(params[&5].get("crane", String::new()).unwrap(), "9000");
+        assert_eq!(params[&5].get("undo", 0).unwrap(), 2);
+        assert_eq!(params[&5].get("redo", 9).unwrap(), 9);
+    }
+
+    #[test]
+    fn day_params_reject_other_table_names() {
+        for name in ["dayseven", "week1", "day-1"] {
+            let config: Config = toml::from_str(&format!("[params.{}]\nx = 1\n", name)).unwrap();
+            let error = config.day_params(&registry()).unwrap_err().to_string();
+            assert_eq!(error, format!("Invalid day in [params.{}]", name));
+        }
+        for name in ["day0", "13", "day25"] {
+            let config: Config = toml::from_str(&format!("[params.{}]\nx = 1\n", name)).unwrap();
+            let error = config.day_params(&registry()).unwrap_err().to_string();
+            assert_eq!(
+                error,
+                format!("Invalid day in [params.{}], supported values: 1..12", name)
+            );
+        }
+    }
+
+    #[test]
+    fn day_params_reject_unknown_keys() {
+        let config: Config = toml::from_str("[params.day01]\ntop_m = 5\n").unwrap();
+        assert_eq!(
+            config.day_params(&registry()).unwrap_err().to_string(),
+            "Unknown parameter 'top_m' in [params.day01], \
+             known parameters: top_n, bins, target, exact_limit"
+        );
+        let config: Config = toml::from_str("[params.8]\ntop = 5\n").unwrap();
+        assert_eq!(
+            config.day_params(&registry()).unwrap_err().to_string(),
+            "Unknown parameter 'top' in [params.8], no parameters are supported"
+        );
+    }
+}
@@ -1,50 +1,1085 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::fmt::Write;
+
 use crate::solution::*;
 
 pub struct Day01 {}
 
-fn compute_line_sum(lines: &str) -> Result<i64> {
+fn parse_items(lines: &str) -> Result<Vec<i64>> {
     let values: std::result::Result<Vec<_>, _> =
-        lines.split("\n").map(|line| line.parse::<i64>()).collect();
-    Ok(values?.iter().sum())
+        lines.lines().map(|line| line.parse::<i64>()).collect();
+    Ok(values?)
+}
+
+fn compute_line_sum(lines: &str) -> Result<i64> {
+    Ok(parse_items(lines)?.iter().sum())
+}
+
+fn get_items_per_elf(input: &str) -> Result<Vec<Vec<i64>>> {
+    input.split("\n\n").map(parse_items).collect()
 }
 
 fn get_calories_per_elf(input: &str) -> Result<Vec<i64>> {
     let lines_per_elf = input.split("\n\n");
-    let calories_per_elf: std::result::Result<Vec<_>, _> =
-        lines_per_elf.map(compute_line_sum).collect();
-    calories_per_elf
+    lines_per_elf.map(compute_line_sum).collect()
+}
+
+/// The elves carrying the most calories, as `(elf_index, total)` pairs sorted by
+/// total, largest first. Ties go to the elf that appears first in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopK {
+    elves: Vec<(usize, i64)>,
 }
 
-pub fn get_top_n_sum(slice: &[i64], n: usize) -> Option<i64> {
-    let mut heap = std::collections::BinaryHeap::<i64>::new();
-    heap.extend(slice.iter());
-    let mut result = 0;
-    for _ in 0..n {
-        result += heap.peek()?;
-        heap.pop();
+impl TopK {
+    pub fn elves(&self) -> &[(usize, i64)] {
+        &self.elves
+    }
+
+    pub fn sum(&self) -> i64 {
+        self.elves.iter().map(|(_, total)| total).sum()
     }
-    return Some(result);
 }
 
-impl Solution for Day01 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 01 - Part 1");
-        let input = std::fs::read_to_string("data/day01.txt")?;
-        let calories_per_elf = get_calories_per_elf(&input);
-        let max_calories = calories_per_elf?.into_iter().max().unwrap_or(0);
-        println!("Maximum calories carried: {}", max_calories);
-        Ok(())
+/// Streaming top-k selection in O(n log k) time and O(k) memory. The heap holds
+/// the current best `k` elves with the weakest one on top, so each new elf only
+/// has to be compared against that one. If fewer than `k` elves are pushed, all
+/// of them are kept.
+pub struct TopKSelector {
+    k: usize,
+    heap: BinaryHeap<Reverse<(i64, Reverse<usize>)>>,
+}
+
+impl TopKSelector {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    pub fn push(&mut self, elf_index: usize, total: i64) {
+        // Higher totals win, and among equal totals the lower index wins.
+        let key = (total, Reverse(elf_index));
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(key));
+        } else if let Some(mut weakest) = self.heap.peek_mut() {
+            if key > weakest.0 {
+                *weakest = Reverse(key);
+            }
+        }
+    }
+
+    pub fn finish(self) -> TopK {
+        let elves = self
+            .heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((total, Reverse(elf_index)))| (elf_index, total))
+            .collect();
+        TopK { elves }
+    }
+}
+
+/// Selects the top `k` elves straight from the puzzle input, one group at a time.
+pub fn top_k_elves(input: &str, k: usize) -> Result<TopK> {
+    let mut selector = TopKSelector::new(k);
+    for (elf_index, lines) in input.split("\n\n").enumerate() {
+        selector.push(elf_index, compute_line_sum(lines)?);
     }
+    Ok(selector.finish())
+}
 
-    fn part2(&self) -> Result<()> {
-        println!("Day 01 - Part 2");
-        let input = std::fs::read_to_string("data/day01.txt")?;
-        let calories_per_elf = get_calories_per_elf(&input);
-        let top_3_sum = get_top_n_sum(&calories_per_elf?, 3);
-        println!(
-            "Calories carried by the top 3 elves: {}",
-            top_3_sum.unwrap()
+/// Elves sorted by total, largest first, as `(rank, elf_index, total)`. Elves with
+/// equal totals share a rank and keep their input order.
+fn rank_elves(calories: &[i64]) -> Vec<(usize, usize, i64)> {
+    let mut order: Vec<usize> = (0..calories.len()).collect();
+    order.sort_by_key(|&idx| Reverse(calories[idx]));
+    let mut ranking: Vec<(usize, usize, i64)> = Vec::with_capacity(order.len());
+    for (position, idx) in order.into_iter().enumerate() {
+        let rank = match ranking.last() {
+            Some(&(rank, _, total)) if total == calories[idx] => rank,
+            _ => position + 1,
+        };
+        ranking.push((rank, idx, calories[idx]));
+    }
+    ranking
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice.
+fn percentile(sorted: &[i64], p: f64) -> i64 {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn median(sorted: &[i64]) -> f64 {
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) as f64 / 2.0
+    } else {
+        sorted[mid] as f64
+    }
+}
+
+/// Splits the range of an ascending, non-empty slice into `bins` equally wide
+/// buckets and returns `(low, high, count)` for each of them. There are never
+/// more buckets than distinct values.
+fn histogram(sorted: &[i64], bins: usize) -> Vec<(i64, i64, usize)> {
+    let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+    let distinct = 1 + sorted.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    let bins = bins.clamp(1, distinct) as i64;
+    let width = ((max - min + 1) + bins - 1) / bins;
+    let mut buckets: Vec<(i64, i64, usize)> = (0..bins)
+        .map(|bin| (min + bin * width, min + (bin + 1) * width - 1, 0))
+        .filter(|(low, _, _)| *low <= max)
+        .collect();
+    for value in sorted {
+        buckets[((value - min) / width) as usize].2 += 1;
+    }
+    buckets
+}
+
+/// Totals carried by more than one elf, largest first, with the elves carrying them.
+fn duplicate_totals(calories: &[i64]) -> Vec<(i64, Vec<usize>)> {
+    let mut by_total: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    for (idx, total) in calories.iter().enumerate() {
+        by_total.entry(*total).or_default().push(idx);
+    }
+    by_total
+        .into_iter()
+        .rev()
+        .filter(|(_, elves)| elves.len() > 1)
+        .collect()
+}
+
+fn inventory_report(calories: &[i64], top_n: usize, bins: usize) -> String {
+    if calories.is_empty() {
+        return "No elves in the inventory".to_owned();
+    }
+    let mut report = String::new();
+    let mut sorted = calories.to_vec();
+    sorted.sort();
+    let total: i64 = calories.iter().sum();
+    let ranking = rank_elves(calories);
+
+    let _ = writeln!(report, "Elves: {}", calories.len());
+    let _ = writeln!(report, "Total calories: {}", total);
+    let _ = writeln!(report, "Mean: {:.2}", total as f64 / calories.len() as f64);
+    let _ = writeln!(report, "Median: {:.1}", median(&sorted));
+    let _ = writeln!(
+        report,
+        "Min: {}, max: {}",
+        sorted[0],
+        sorted[sorted.len() - 1]
+    );
+    let percentiles: Vec<String> = [10.0, 25.0, 50.0, 75.0, 90.0, 99.0]
+        .iter()
+        .map(|p| format!("p{}={}", p, percentile(&sorted, *p)))
+        .collect();
+    let _ = writeln!(report, "Percentiles: {}", percentiles.join(", "));
+
+    let mut selector = TopKSelector::new(top_n);
+    for (idx, total) in calories.iter().enumerate() {
+        selector.push(idx, *total);
+    }
+    let top = selector.finish();
+    let _ = writeln!(
+        report,
+        "\nTop {} elves carry {} calories:",
+        top.elves().len(),
+        top.sum()
+    );
+    for (position, (idx, total)) in top.elves().iter().enumerate() {
+        let _ = writeln!(
+            report,
+            "  {:>3}. elf {:<5} {}",
+            position + 1,
+            idx + 1,
+            total
         );
+    }
+
+    let buckets = histogram(&sorted, bins);
+    let largest_bucket = buckets
+        .iter()
+        .map(|(_, _, count)| *count)
+        .max()
+        .unwrap_or(1);
+    let _ = writeln!(report, "\nHistogram:");
+    for (low, high, count) in buckets {
+        let bar = "#".repeat((count * 40).div_ceil(largest_bucket));
+        let _ = writeln!(report, "  {:>8} - {:<8} |{:<40}| {}", low, high, bar, count);
+    }
+
+    let duplicates = duplicate_totals(calories);
+    let _ = writeln!(report, "\nDuplicate totals:");
+    if duplicates.is_empty() {
+        let _ = writeln!(report, "  none");
+    }
+    for (total, elves) in duplicates {
+        let elves: Vec<String> = elves.iter().map(|idx| (idx + 1).to_string()).collect();
+        let _ = writeln!(report, "  {}: elves {}", total, elves.join(", "));
+    }
+
+    let _ = writeln!(report, "\nRanking:");
+    let _ = writeln!(report, "  {:>5} {:>5} {:>10}", "Rank", "Elf", "Calories");
+    for (rank, idx, total) in ranking {
+        let _ = writeln!(report, "  {:>5} {:>5} {:>10}", rank, idx + 1, total);
+    }
+    report.trim_end().to_owned()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RebalanceGoal {
+    /// Make the most heavily loaded elf carry as little as possible.
+    MinimiseMax,
+    /// Get every elf down to at most this many calories.
+    Target(i64),
+}
+
+/// A food item, identified by the elf that originally carries it and its
+/// position in that elf's list.
+#[derive(Debug, Clone, Copy)]
+struct Item {
+    owner: usize,
+    index: usize,
+    calories: i64,
+}
+
+/// All items, largest first, which is the order both solvers place them in.
+fn flatten_items(items_per_elf: &[Vec<i64>]) -> Vec<Item> {
+    let mut items: Vec<Item> = items_per_elf
+        .iter()
+        .enumerate()
+        .flat_map(|(owner, items)| {
+            items.iter().enumerate().map(move |(index, calories)| Item {
+                owner,
+                index,
+                calories: *calories,
+            })
+        })
+        .collect();
+    items.sort_by_key(|item| Reverse(item.calories));
+    items
+}
+
+fn loads_of(items: &[Item], assignment: &[usize], elves: usize) -> Vec<i64> {
+    let mut loads = vec![0; elves];
+    for (item, elf) in items.iter().zip(assignment) {
+        loads[*elf] += item.calories;
+    }
+    loads
+}
+
+#[derive(Debug)]
+struct RebalancePlan {
+    solver: &'static str,
+    items: Vec<Item>,
+    assignment: Vec<usize>,
+    loads_before: Vec<i64>,
+    loads_after: Vec<i64>,
+    /// False if the goal was a target that could not be reached.
+    reached: bool,
+}
+
+impl RebalancePlan {
+    fn new(solver: &'static str, items: Vec<Item>, assignment: Vec<usize>, elves: usize) -> Self {
+        let owners: Vec<usize> = items.iter().map(|item| item.owner).collect();
+        let loads_before = loads_of(&items, &owners, elves);
+        let loads_after = loads_of(&items, &assignment, elves);
+        Self {
+            solver,
+            items,
+            assignment,
+            loads_before,
+            loads_after,
+            reached: true,
+        }
+    }
+
+    /// `(item, destination)` for every item that changes hands, in input order.
+    fn transfers(&self) -> Vec<(Item, usize)> {
+        let mut transfers: Vec<(Item, usize)> = self
+            .items
+            .iter()
+            .zip(&self.assignment)
+            .filter(|(item, elf)| item.owner != **elf)
+            .map(|(item, elf)| (*item, *elf))
+            .collect();
+        transfers.sort_by_key(|(item, _)| (item.owner, item.index));
+        transfers
+    }
+}
+
+enum ExactOutcome {
+    Solved(Vec<usize>),
+    /// The search finished without finding any assignment that meets the goal.
+    Infeasible,
+    /// The search ran out of budget, with the best assignment found so far.
+    GaveUp(Option<Vec<usize>>),
+}
+
+const EXACT_NODE_BUDGET: usize = 5_000_000;
+
+/// Branch and bound over item placements. Candidate assignments are ranked by
+/// (maximum load, transfers) when minimising the maximum, and by (transfers,
+/// maximum load) when aiming for a target, so among equally good plans the one
+/// that moves the fewest items wins.
+struct ExactSearch<'a> {
+    items: &'a [Item],
+    goal: RebalanceGoal,
+    cancel: &'a CancellationToken,
+    budget: usize,
+    loads: Vec<i64>,
+    /// Items at or after the current depth still owned by each elf.
+    remaining_owned: Vec<usize>,
+    assignment: Vec<usize>,
+    lower_bound: i64,
+    best: Option<((i64, i64), Vec<usize>)>,
+    nodes: usize,
+}
+
+impl<'a> ExactSearch<'a> {
+    fn run(
+        items: &'a [Item],
+        elves: usize,
+        goal: RebalanceGoal,
+        budget: usize,
+        cancel: &'a CancellationToken,
+    ) -> Result<ExactOutcome> {
+        let total: i64 = items.iter().map(|item| item.calories).sum();
+        let largest = items.first().map(|item| item.calories).unwrap_or(0);
+        let mut remaining_owned = vec![0; elves];
+        for item in items {
+            remaining_owned[item.owner] += 1;
+        }
+        let mut search = ExactSearch {
+            items,
+            goal,
+            cancel,
+            budget,
+            loads: vec![0; elves],
+            remaining_owned,
+            assignment: vec![0; items.len()],
+            lower_bound: largest.max((total + elves as i64 - 1) / elves as i64),
+            best: None,
+            nodes: 0,
+        };
+        search.place(0, 0, 0)?;
+        Ok(if search.nodes > budget {
+            ExactOutcome::GaveUp(search.best.map(|(_, assignment)| assignment))
+        } else if let Some((_, assignment)) = search.best {
+            ExactOutcome::Solved(assignment)
+        } else {
+            ExactOutcome::Infeasible
+        })
+    }
+
+    fn key(&self, max_load: i64, moves: usize) -> (i64, i64) {
+        match self.goal {
+            RebalanceGoal::MinimiseMax => (max_load, moves as i64),
+            RebalanceGoal::Target(_) => (moves as i64, max_load),
+        }
+    }
+
+    fn place(&mut self, depth: usize, moves: usize, max_load: i64) -> Result<()> {
+        self.nodes += 1;
+        if self.nodes > self.budget {
+            return Ok(());
+        }
+        if self.nodes.is_multiple_of(4096) {
+            self.cancel.check()?;
+        }
+        if let Some((best_key, _)) = &self.best {
+            if self.key(max_load.max(self.lower_bound), moves) >= *best_key {
+                return Ok(());
+            }
+        }
+        if depth == self.items.len() {
+            self.best = Some((self.key(max_load, moves), self.assignment.clone()));
+            return Ok(());
+        }
+
+        let item = self.items[depth];
+        self.remaining_owned[item.owner] -= 1;
+        // Elves that own none of the remaining items and carry the same load
+        // are interchangeable, so only the first of them needs to be tried.
+        let mut tried_loads: Vec<i64> = Vec::new();
+        let candidates =
+            std::iter::once(item.owner).chain((0..self.loads.len()).filter(|e| *e != item.owner));
+        for elf in candidates {
+            if elf != item.owner && self.remaining_owned[elf] == 0 {
+                if tried_loads.contains(&self.loads[elf]) {
+                    continue;
+                }
+                tried_loads.push(self.loads[elf]);
+            }
+            let new_load = self.loads[elf] + item.calories;
+            if let RebalanceGoal::Target(target) = self.goal {
+                if new_load > target {
+                    continue;
+                }
+            }
+            self.loads[elf] = new_load;
+            self.assignment[depth] = elf;
+            let moved = usize::from(elf != item.owner);
+            let result = self.place(depth + 1, moves + moved, max_load.max(new_load));
+            self.loads[elf] -= item.calories;
+            result?;
+            if self.nodes > self.budget {
+                break;
+            }
+        }
+        self.remaining_owned[item.owner] += 1;
         Ok(())
     }
 }
+
+/// Longest processing time first: hand out items largest first, each to the
+/// currently lightest elf. The resulting groups are then matched to the elves
+/// that already carry most of their items to keep the number of transfers low.
+fn lpt_assignment(items: &[Item], elves: usize) -> Vec<usize> {
+    let mut heap: BinaryHeap<Reverse<(i64, usize)>> =
+        (0..elves).map(|group| Reverse((0, group))).collect();
+    let mut groups = Vec::with_capacity(items.len());
+    for item in items {
+        let Reverse((load, group)) = heap.pop().unwrap();
+        groups.push(group);
+        heap.push(Reverse((load + item.calories, group)));
+    }
+
+    let mut overlap: HashMap<(usize, usize), usize> = HashMap::new();
+    for (item, group) in items.iter().zip(&groups) {
+        *overlap.entry((*group, item.owner)).or_default() += 1;
+    }
+    let mut pairs: Vec<((usize, usize), usize)> = overlap.into_iter().collect();
+    pairs.sort_by_key(|&((group, elf), count)| (Reverse(count), group, elf));
+    let mut group_to_elf: Vec<Option<usize>> = vec![None; elves];
+    let mut elf_taken = vec![false; elves];
+    for ((group, elf), _) in pairs {
+        if group_to_elf[group].is_none() && !elf_taken[elf] {
+            group_to_elf[group] = Some(elf);
+            elf_taken[elf] = true;
+        }
+    }
+    let mut free_elves = (0..elves).filter(|elf| !elf_taken[*elf]);
+    for slot in group_to_elf.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = free_elves.next();
+    }
+    groups
+        .into_iter()
+        .map(|group| group_to_elf[group].unwrap())
+        .collect()
+}
+
+/// Relieves overloaded elves one at a time. Each step moves the item that gets
+/// the elf under `target` in one go if there is one, otherwise its largest item
+/// that still fits, to the lightest other elf. Returns whether the target was
+/// reached.
+fn greedy_target_assignment(
+    items: &[Item],
+    elves: usize,
+    target: i64,
+    cancel: &CancellationToken,
+) -> Result<(Vec<usize>, bool)> {
+    let mut assignment: Vec<usize> = items.iter().map(|item| item.owner).collect();
+    let mut loads = loads_of(items, &assignment, elves);
+    // Each elf's items ordered by calories. Among equal items the one listed
+    // last in `items` comes first, so ties are broken the same way every time.
+    let mut held: Vec<BTreeSet<(i64, Reverse<usize>)>> = vec![BTreeSet::new(); elves];
+    for (idx, item) in items.iter().enumerate() {
+        held[item.owner].insert((item.calories, Reverse(idx)));
+    }
+    let mut overloaded: Vec<usize> = (0..elves).filter(|elf| loads[*elf] > target).collect();
+    overloaded.sort_by_key(|elf| Reverse(loads[*elf]));
+    let mut reached = true;
+    for elf in overloaded {
+        while loads[elf] > target {
+            cancel.check()?;
+            let lightest = (0..elves)
+                .filter(|other| *other != elf)
+                .min_by_key(|other| loads[*other]);
+            let Some(lightest) = lightest else {
+                break;
+            };
+            let room = target - loads[lightest];
+            let excess = loads[elf] - target;
+            // The smallest item that fits and covers the excess, otherwise the
+            // largest item that fits.
+            let covering = if excess <= room {
+                held[elf]
+                    .range((excess, Reverse(usize::MAX))..=(room, Reverse(0)))
+                    .next()
+            } else {
+                None
+            };
+            let choice = covering
+                .or_else(|| held[elf].range(..=(room, Reverse(0))).next_back())
+                .copied();
+            let Some((calories, Reverse(idx))) = choice else {
+                break;
+            };
+            held[elf].remove(&(calories, Reverse(idx)));
+            held[lightest].insert((calories, Reverse(idx)));
+            assignment[idx] = lightest;
+            loads[elf] -= calories;
+            loads[lightest] += calories;
+        }
+        reached &= loads[elf] <= target;
+    }
+    Ok((assignment, reached))
+}
+
+fn greedy_plan(
+    items: Vec<Item>,
+    elves: usize,
+    goal: RebalanceGoal,
+    cancel: &CancellationToken,
+) -> Result<RebalancePlan> {
+    let owners: Vec<usize> = items.iter().map(|item| item.owner).collect();
+    match goal {
+        RebalanceGoal::MinimiseMax => {
+            let assignment = lpt_assignment(&items, elves);
+            let current_max = loads_of(&items, &owners, elves).into_iter().max();
+            let lpt_max = loads_of(&items, &assignment, elves).into_iter().max();
+            if lpt_max < current_max {
+                Ok(RebalancePlan::new("greedy (LPT)", items, assignment, elves))
+            } else {
+                Ok(RebalancePlan::new("greedy (LPT)", items, owners, elves))
+            }
+        }
+        RebalanceGoal::Target(target) => {
+            let (assignment, reached) = greedy_target_assignment(&items, elves, target, cancel)?;
+            let mut plan = RebalancePlan::new("greedy", items, assignment, elves);
+            plan.reached = reached;
+            Ok(plan)
+        }
+    }
+}
+
+/// Orders plans the way `ExactSearch` does, with plans that miss the target last.
+fn plan_key(plan: &RebalancePlan, goal: RebalanceGoal) -> (bool, i64, i64) {
+    let max_load = plan.loads_after.iter().copied().max().unwrap_or(0);
+    let moves = plan.transfers().len() as i64;
+    match goal {
+        RebalanceGoal::MinimiseMax => (!plan.reached, max_load, moves),
+        RebalanceGoal::Target(_) => (!plan.reached, moves, max_load),
+    }
+}
+
+fn plan_rebalance(
+    items_per_elf: &[Vec<i64>],
+    goal: RebalanceGoal,
+    exact_limit: usize,
+    node_budget: usize,
+    cancel: &CancellationToken,
+) -> Result<RebalancePlan> {
+    let elves = items_per_elf.len();
+    let items = flatten_items(items_per_elf);
+    let owners: Vec<usize> = items.iter().map(|item| item.owner).collect();
+
+    let mut incumbent = None;
+    if items.len() <= exact_limit {
+        match ExactSearch::run(&items, elves, goal, node_budget, cancel)? {
+            ExactOutcome::Solved(assignment) => {
+                return Ok(RebalancePlan::new("exact", items, assignment, elves))
+            }
+            ExactOutcome::Infeasible => {
+                let mut plan = RebalancePlan::new("exact", items, owners, elves);
+                plan.reached = false;
+                return Ok(plan);
+            }
+            ExactOutcome::GaveUp(best) => incumbent = best,
+        }
+    }
+
+    let greedy = greedy_plan(items.clone(), elves, goal, cancel)?;
+    match incumbent {
+        Some(assignment) => {
+            let partial = RebalancePlan::new("exact (budget exhausted)", items, assignment, elves);
+            if plan_key(&partial, goal) < plan_key(&greedy, goal) {
+                Ok(partial)
+            } else {
+                Ok(greedy)
+            }
+        }
+        None => Ok(greedy),
+    }
+}
+
+fn render_plan(plan: &RebalancePlan, goal: RebalanceGoal) -> String {
+    let mut output = String::new();
+    let _ = match goal {
+        RebalanceGoal::MinimiseMax => writeln!(output, "Goal: minimise the maximum load"),
+        RebalanceGoal::Target(target) => {
+            writeln!(
+                output,
+                "Goal: every elf carries at most {} calories",
+                target
+            )
+        }
+    };
+    let _ = writeln!(output, "Solver: {}", plan.solver);
+    let _ = writeln!(
+        output,
+        "Items: {} across {} elves",
+        plan.items.len(),
+        plan.loads_before.len()
+    );
+    let _ = writeln!(
+        output,
+        "Maximum load: {} -> {}",
+        plan.loads_before.iter().max().unwrap_or(&0),
+        plan.loads_after.iter().max().unwrap_or(&0)
+    );
+    if !plan.reached {
+        let still_over: Vec<String> = match goal {
+            RebalanceGoal::Target(target) => (0..plan.loads_after.len())
+                .filter(|elf| plan.loads_after[*elf] > target)
+                .map(|elf| (elf + 1).to_string())
+                .collect(),
+            RebalanceGoal::MinimiseMax => Vec::new(),
+        };
+        let _ = writeln!(
+            output,
+            "Target not reachable, elves still above it: {}",
+            still_over.join(", ")
+        );
+    }
+
+    let transfers = plan.transfers();
+    let _ = writeln!(output, "\nTransfers: {}", transfers.len());
+    for (item, to) in &transfers {
+        let _ = writeln!(
+            output,
+            "  move item {} ({} calories) from elf {} to elf {}",
+            item.index + 1,
+            item.calories,
+            item.owner + 1,
+            to + 1
+        );
+    }
+    if !transfers.is_empty() {
+        let _ = writeln!(output, "\nChanged loads:");
+        for elf in 0..plan.loads_before.len() {
+            if plan.loads_before[elf] != plan.loads_after[elf] {
+                let _ = writeln!(
+                    output,
+                    "  elf {}: {} -> {}",
+                    elf + 1,
+                    plan.loads_before[elf],
+                    plan.loads_after[elf]
+                );
+            }
+        }
+    }
+    output.trim_end().to_owned()
+}
+
+impl Solution for Day01 {
+    fn part1(&self, input: &str, _ctx: &Context) -> Result<String> {
+        let calories_per_elf = get_calories_per_elf(input)?;
+        let max_calories = calories_per_elf.into_iter().max().unwrap_or(0);
+        Ok(max_calories.to_string())
+    }
+
+    fn part2(&self, input: &str, ctx: &Context) -> Result<String> {
+        let top_n = ctx.params.get("top_n", 3)?;
+        Ok(top_k_elves(input, top_n)?.sum().to_string())
+    }
+
+    fn modes(&self) -> &'static [&'static str] {
+        &["report", "rebalance"]
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["top_n", "bins", "target", "exact_limit"]
+    }
+
+    fn run_mode(&self, mode: &str, input: &str, ctx: &Context) -> Result<String> {
+        match mode {
+            "report" => {
+                let top_n = ctx.params.get("top_n", 3)?;
+                let bins = ctx.params.get("bins", 10)?;
+                let calories_per_elf = get_calories_per_elf(input)?;
+                Ok(inventory_report(&calories_per_elf, top_n, bins))
+            }
+            "rebalance" => {
+                let goal = match ctx.params.get_opt("target")? {
+                    Some(target) => RebalanceGoal::Target(target),
+                    None => RebalanceGoal::MinimiseMax,
+                };
+                let exact_limit = ctx.params.get("exact_limit", 12)?;
+                let items_per_elf = get_items_per_elf(input)?;
+                let plan = plan_rebalance(
+                    &items_per_elf,
+                    goal,
+                    exact_limit,
+                    EXACT_NODE_BUDGET,
+                    &ctx.cancel,
+                )?;
+                Ok(render_plan(&plan, goal))
+            }
+            _ => Err(unsupported_mode(mode, self.modes())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn top_k(totals: &[i64], k: usize) -> TopK {
+        let mut selector = TopKSelector::new(k);
+        for (idx, total) in totals.iter().enumerate() {
+            selector.push(idx, *total);
+        }
+        selector.finish()
+    }
+
+    #[test]
+    fn selects_largest_totals_in_order() {
+        let top = top_k(&[6000, 4000, 11000, 24000, 10000], 3);
+        assert_eq!(top.elves(), &[(3, 24000), (2, 11000), (4, 10000)]);
+        assert_eq!(top.sum(), 45000);
+    }
+
+    #[test]
+    fn ties_prefer_earlier_elves() {
+        let top = top_k(&[5, 7, 5, 7, 5], 3);
+        assert_eq!(top.elves(), &[(1, 7), (3, 7), (0, 5)]);
+    }
+
+    #[test]
+    fn ties_at_the_boundary_keep_the_first_seen() {
+        let top = top_k(&[1, 3, 3, 3, 2], 2);
+        assert_eq!(top.elves(), &[(1, 3), (2, 3)]);
+        assert_eq!(top.sum(), 6);
+    }
+
+    #[test]
+    fn all_equal_totals() {
+        let top = top_k(&[9; 6], 4);
+        assert_eq!(top.elves(), &[(0, 9), (1, 9), (2, 9), (3, 9)]);
+    }
+
+    #[test]
+    fn k_larger_than_population_keeps_everyone() {
+        let top = top_k(&[3, 1, 2], 10);
+        assert_eq!(top.elves(), &[(0, 3), (2, 2), (1, 1)]);
+        assert_eq!(top.sum(), 6);
+    }
+
+    #[test]
+    fn huge_k_does_not_allocate_up_front() {
+        let top = top_k(&[3, 1, 2], usize::MAX);
+        assert_eq!(top.elves(), &[(0, 3), (2, 2), (1, 1)]);
+    }
+
+    #[test]
+    fn k_zero_is_empty() {
+        let top = top_k(&[3, 1, 2], 0);
+        assert!(top.elves().is_empty());
+        assert_eq!(top.sum(), 0);
+    }
+
+    #[test]
+    fn streams_groups_from_input() {
+        let input = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000";
+        let top = top_k_elves(input, 3).unwrap();
+        assert_eq!(top.elves(), &[(3, 24000), (2, 11000), (4, 10000)]);
+        assert_eq!(top.sum(), 45000);
+        assert!(top_k_elves("1\nx", 1).is_err());
+    }
+
+    // LPT ends up at 9 here, but 8 is possible: 5+3, 5+3 and 4+2+2.
+    fn lopsided() -> Vec<Vec<i64>> {
+        vec![vec![5, 5, 4], vec![3, 3, 2], vec![2]]
+    }
+
+    /// Calls `visit` with every way to hand out `count` items to `elves` elves.
+    fn for_each_assignment(count: usize, elves: usize, mut visit: impl FnMut(&[usize])) {
+        let mut assignment = vec![0; count];
+        loop {
+            visit(&assignment);
+            let Some(idx) = assignment.iter().position(|elf| elf + 1 < elves) else {
+                return;
+            };
+            assignment[idx] += 1;
+            assignment[..idx].fill(0);
+        }
+    }
+
+    fn brute_force_max(items: &[Item], elves: usize) -> i64 {
+        let mut best = i64::MAX;
+        for_each_assignment(items.len(), elves, |assignment| {
+            best = best.min(
+                loads_of(items, assignment, elves)
+                    .into_iter()
+                    .max()
+                    .unwrap(),
+            );
+        });
+        best
+    }
+
+    fn brute_force_transfers(items: &[Item], elves: usize, target: i64) -> usize {
+        let mut best = usize::MAX;
+        for_each_assignment(items.len(), elves, |assignment| {
+            if loads_of(items, assignment, elves)
+                .iter()
+                .all(|load| *load <= target)
+            {
+                let moves = items
+                    .iter()
+                    .zip(assignment)
+                    .filter(|(item, elf)| item.owner != **elf)
+                    .count();
+                best = best.min(moves);
+            }
+        });
+        best
+    }
+
+    fn assert_valid(plan: &RebalancePlan, items_per_elf: &[Vec<i64>]) {
+        let totals: Vec<i64> = items_per_elf
+            .iter()
+            .map(|items| items.iter().sum())
+            .collect();
+        assert_eq!(plan.loads_before, totals);
+        assert_eq!(plan.assignment.len(), plan.items.len());
+        assert!(plan.assignment.iter().all(|elf| *elf < items_per_elf.len()));
+        assert_eq!(
+            plan.loads_after,
+            loads_of(&plan.items, &plan.assignment, items_per_elf.len())
+        );
+        assert_eq!(
+            plan.loads_after.iter().sum::<i64>(),
+            totals.iter().sum::<i64>()
+        );
+    }
+
+    #[test]
+    fn exact_search_finds_the_minimum() {
+        let items_per_elf = lopsided();
+        let cancel = CancellationToken::default();
+        let plan = plan_rebalance(
+            &items_per_elf,
+            RebalanceGoal::MinimiseMax,
+            12,
+            EXACT_NODE_BUDGET,
+            &cancel,
+        )
+        .unwrap();
+        assert_valid(&plan, &items_per_elf);
+        assert_eq!(plan.solver, "exact");
+        let items = flatten_items(&items_per_elf);
+        assert_eq!(brute_force_max(&items, 3), 8);
+        assert_eq!(plan.loads_after.iter().max(), Some(&8));
+
+        let greedy = plan_rebalance(
+            &items_per_elf,
+            RebalanceGoal::MinimiseMax,
+            0,
+            EXACT_NODE_BUDGET,
+            &cancel,
+        )
+        .unwrap();
+        assert_eq!(greedy.solver, "greedy (LPT)");
+        assert_eq!(greedy.loads_after.iter().max(), Some(&9));
+    }
+
+    #[test]
+    fn exact_search_meets_targets_with_fewest_transfers() {
+        let items_per_elf = lopsided();
+        let cancel = CancellationToken::default();
+        let plan = plan_rebalance(
+            &items_per_elf,
+            RebalanceGoal::Target(8),
+            12,
+            EXACT_NODE_BUDGET,
+            &cancel,
+        )
+        .unwrap();
+        assert_valid(&plan, &items_per_elf);
+        assert!(plan.reached);
+        assert!(plan.loads_after.iter().all(|load| *load <= 8));
+        assert_eq!(
+            plan.transfers().len(),
+            brute_force_transfers(&plan.items, 3, 8)
+        );
+
+        let impossible = plan_rebalance(
+            &items_per_elf,
+            RebalanceGoal::Target(7),
+            12,
+            EXACT_NODE_BUDGET,
+            &cancel,
+        )
+        .unwrap();
+        assert!(!impossible.reached);
+        assert!(impossible.transfers().is_empty());
+    }
+
+    #[test]
+    fn lpt_stays_within_its_bound() {
+        let cases: [&[i64]; 4] = [
+            &[5, 5, 4, 3, 3, 2, 2],
+            &[7, 7, 6, 6, 5, 5, 4, 4, 4],
+            &[9, 1, 1, 1, 1, 1, 1],
+            &[3, 3, 2, 2, 2],
+        ];
+        for calories in cases {
+            for elves in 2..=3 {
+                let mut items_per_elf = vec![calories.to_vec()];
+                items_per_elf.resize(elves, Vec::new());
+                let items = flatten_items(&items_per_elf);
+                let lpt = loads_of(&items, &lpt_assignment(&items, elves), elves)
+                    .into_iter()
+                    .max()
+                    .unwrap();
+                let optimum = brute_force_max(&items, elves);
+                // Graham: LPT <= (4/3 - 1/(3m)) * OPT.
+                let m = elves as i64;
+                assert!(
+                    3 * m * lpt <= (4 * m - 1) * optimum,
+                    "{:?} over {} elves: LPT {} vs optimum {}",
+                    calories,
+                    elves,
+                    lpt,
+                    optimum
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn giving_up_returns_a_valid_plan() {
+        let items_per_elf = lopsided();
+        let cancel = CancellationToken::default();
+        let mut used_incumbent = false;
+        for budget in 0..200 {
+            for goal in [RebalanceGoal::MinimiseMax, RebalanceGoal::Target(8)] {
+                let plan = plan_rebalance(&items_per_elf, goal, 12, budget, &cancel).unwrap();
+                assert_valid(&plan, &items_per_elf);
+                if goal == RebalanceGoal::Target(8) && plan.reached {
+                    assert!(plan.loads_after.iter().all(|load| *load <= 8));
+                }
+                if plan.solver == "exact (budget exhausted)" {
+                    used_incumbent = true;
+                    let greedy = greedy_plan(plan.items.clone(), 3, goal, &cancel).unwrap();
+                    assert!(plan_key(&plan, goal) < plan_key(&greedy, goal));
+                }
+            }
+        }
+        // The greedy target solver gets stuck at 9, so a partial exact search
+        // that already met the target must win.
+        assert!(used_incumbent);
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let sorted = [10, 20, 30, 40, 50];
+        assert_eq!(percentile(&sorted, 0.0), 10);
+        assert_eq!(percentile(&sorted, 20.0), 10);
+        assert_eq!(percentile(&sorted, 21.0), 20);
+        assert_eq!(percentile(&sorted, 50.0), 30);
+        assert_eq!(percentile(&sorted, 100.0), 50);
+        assert_eq!(percentile(&[10, 20, 30, 40], 50.0), 20);
+        assert_eq!(percentile(&[10, 20, 30, 40], 51.0), 30);
+        assert_eq!(percentile(&[7], 1.0), 7);
+        assert_eq!(percentile(&[7], 99.0), 7);
+    }
+
+    #[test]
+    fn medians_of_odd_and_even_counts() {
+        assert_eq!(median(&[1, 2, 9]), 2.0);
+        assert_eq!(median(&[1, 2, 4, 9]), 3.0);
+        assert_eq!(median(&[1, 2]), 1.5);
+        assert_eq!(median(&[5, 5, 5, 5]), 5.0);
+        assert_eq!(median(&[7]), 7.0);
+    }
+
+    #[test]
+    fn histograms() {
+        assert_eq!(
+            histogram(&[1, 2, 3, 4, 10], 3),
+            vec![(1, 4, 4), (5, 8, 0), (9, 12, 1)]
+        );
+        assert_eq!(histogram(&[1, 2, 3, 4], 2), vec![(1, 2, 2), (3, 4, 2)]);
+        assert_eq!(histogram(&[1, 2, 3, 4], 0), vec![(1, 4, 4)]);
+        assert_eq!(histogram(&[7], 10), vec![(7, 7, 1)]);
+        assert_eq!(histogram(&[3, 3, 3], 5), vec![(3, 3, 3)]);
+    }
+
+    #[test]
+    fn huge_bin_counts_are_capped() {
+        assert_eq!(
+            histogram(&[1, 1, 100], usize::MAX),
+            vec![(1, 50, 2), (51, 100, 1)]
+        );
+    }
+
+    #[test]
+    fn ranks_share_ties() {
+        assert_eq!(
+            rank_elves(&[5, 9, 5, 1, 9]),
+            vec![(1, 1, 9), (1, 4, 9), (3, 0, 5), (3, 2, 5), (5, 3, 1)]
+        );
+        assert_eq!(rank_elves(&[4, 4]), vec![(1, 0, 4), (1, 1, 4)]);
+        assert_eq!(rank_elves(&[3]), vec![(1, 0, 3)]);
+    }
+
+    #[test]
+    fn duplicates_are_listed_largest_first() {
+        assert_eq!(
+            duplicate_totals(&[5, 9, 5, 1, 9, 5]),
+            vec![(9, vec![1, 4]), (5, vec![0, 2, 5])]
+        );
+        assert!(duplicate_totals(&[1, 2, 3]).is_empty());
+        assert!(duplicate_totals(&[3]).is_empty());
+    }
+
+    #[test]
+    fn report_for_a_single_elf() {
+        let report = inventory_report(&[42], 3, usize::MAX);
+        assert!(report.contains("Median: 42.0"));
+        assert!(report.contains("Percentiles: p10=42, p25=42, p50=42, p75=42, p90=42, p99=42"));
+        assert!(report.contains("Top 1 elves carry 42 calories"));
+        assert!(report.contains("       42 - 42       |"));
+        assert_eq!(inventory_report(&[], 3, 10), "No elves in the inventory");
+    }
+
+    #[test]
+    fn greedy_target_handles_many_items() {
+        // One elf carries everything, so every item but the first few moves.
+        let items_per_elf: Vec<Vec<i64>> =
+            std::iter::once((1..=20_000).map(|i| i % 97 + 1).collect())
+                .chain(std::iter::repeat_n(Vec::new(), 99))
+                .collect();
+        let total: i64 = items_per_elf[0].iter().sum();
+        let target = total / 100 + 200;
+        let cancel = CancellationToken::default();
+        let plan = plan_rebalance(
+            &items_per_elf,
+            RebalanceGoal::Target(target),
+            0,
+            EXACT_NODE_BUDGET,
+            &cancel,
+        )
+        .unwrap();
+        assert_valid(&plan, &items_per_elf);
+        assert!(plan.reached);
+        assert!(plan.loads_after.iter().all(|load| *load <= target));
+    }
+
+    #[test]
+    fn greedy_target_stops_when_cancelled() {
+        let cancel = CancellationToken::default();
+        cancel.cancel();
+        let items = flatten_items(&lopsided());
+        let error = greedy_target_assignment(&items, 3, 8, &cancel).unwrap_err();
+        assert!(error.is::<Cancelled>());
+    }
+}
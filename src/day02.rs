@@ -1,125 +1,1022 @@
+use std::fmt::Write;
+
 use crate::solution::*;
+use simple_error::SimpleError;
+
 pub struct Day02 {}
 
-#[derive(Clone, Copy)]
-enum Move {
-    Rock,
-    Paper,
-    Scissors,
+/// One move of a rule set: its name, the letters standing for it in the
+/// opponent's and the player's column, and the points for choosing it.
+#[derive(Debug, Clone, Copy)]
+struct MoveSpec {
+    name: &'static str,
+    opponent: char,
+    player: char,
+    score: i64,
+}
+
+const RPS_MOVES: &[MoveSpec] = &[
+    MoveSpec {
+        name: "Rock",
+        opponent: 'A',
+        player: 'X',
+        score: 1,
+    },
+    MoveSpec {
+        name: "Paper",
+        opponent: 'B',
+        player: 'Y',
+        score: 2,
+    },
+    MoveSpec {
+        name: "Scissors",
+        opponent: 'C',
+        player: 'Z',
+        score: 3,
+    },
+];
+
+const RPS_BEATS: &[(&str, &str)] = &[
+    ("Rock", "Scissors"),
+    ("Paper", "Rock"),
+    ("Scissors", "Paper"),
+];
+
+// Classic letters keep their meaning so plain RPS guides parse unchanged.
+const RPSLS_MOVES: &[MoveSpec] = &[
+    MoveSpec {
+        name: "Rock",
+        opponent: 'A',
+        player: 'X',
+        score: 1,
+    },
+    MoveSpec {
+        name: "Paper",
+        opponent: 'B',
+        player: 'Y',
+        score: 2,
+    },
+    MoveSpec {
+        name: "Scissors",
+        opponent: 'C',
+        player: 'Z',
+        score: 3,
+    },
+    MoveSpec {
+        name: "Lizard",
+        opponent: 'D',
+        player: 'W',
+        score: 4,
+    },
+    MoveSpec {
+        name: "Spock",
+        opponent: 'E',
+        player: 'V',
+        score: 5,
+    },
+];
+
+const RPSLS_BEATS: &[(&str, &str)] = &[
+    ("Scissors", "Paper"),
+    ("Paper", "Rock"),
+    ("Rock", "Lizard"),
+    ("Lizard", "Spock"),
+    ("Spock", "Scissors"),
+    ("Scissors", "Lizard"),
+    ("Lizard", "Paper"),
+    ("Paper", "Spock"),
+    ("Spock", "Rock"),
+    ("Rock", "Scissors"),
+];
+
+/// Names accepted by the `rules` parameter.
+const BUILTIN_RULES: &[&str] = &["rps", "rpsls"];
+
+/// A move, as an index into its rule set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Move(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Loss,
+    Draw,
+    Win,
 }
 
-impl Move {
-    fn from_char(c: char) -> Self {
+const OUTCOMES: [Outcome; 3] = [Outcome::Loss, Outcome::Draw, Outcome::Win];
+
+impl Outcome {
+    fn from_char(c: char) -> Result<Self> {
         match c {
-            'A' => Move::Rock,
-            'B' => Move::Paper,
-            'C' => Move::Scissors,
-            'X' => Move::Rock,
-            'Y' => Move::Paper,
-            'Z' => Move::Scissors,
-            _ => panic!("Invalid move spec: '{}'", c),
+            'X' => Ok(Outcome::Loss),
+            'Y' => Ok(Outcome::Draw),
+            'Z' => Ok(Outcome::Win),
+            _ => Err(Box::new(SimpleError::new(format!(
+                "Invalid outcome spec: '{}'",
+                c
+            )))),
         }
     }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Outcome::Loss => "Loss",
+            Outcome::Draw => "Draw",
+            Outcome::Win => "Win",
+        }
+    }
+
     fn score(&self) -> i64 {
         match self {
-            Move::Rock => 1,
-            Move::Paper => 2,
-            Move::Scissors => 3,
+            Outcome::Loss => 0,
+            Outcome::Draw => 3,
+            Outcome::Win => 6,
         }
     }
 }
 
-enum Outcome {
-    Loss,
-    Draw,
-    Win,
+/// An odd-sized cyclic game: every move beats exactly half of the others and
+/// loses to the rest, so no move dominates.
+#[derive(Debug)]
+struct RuleSet {
+    moves: Vec<MoveSpec>,
+    /// `beats[a][b]` is true if move `a` beats move `b`.
+    beats: Vec<Vec<bool>>,
 }
-impl Outcome {
-    fn from_char(c: char) -> Self {
-        match c {
-            'X' => Outcome::Loss,
-            'Y' => Outcome::Draw,
-            'Z' => Outcome::Win,
-            _ => panic!("Invalid outcome spec: '{}'", c),
-        }
-    }
-}
-
-fn choose_move(opponent: Move, outcome: Outcome) -> Move {
-    match opponent {
-        Move::Rock => match outcome {
-            Outcome::Loss => Move::Scissors,
-            Outcome::Draw => Move::Rock,
-            Outcome::Win => Move::Paper,
-        },
-        Move::Paper => match outcome {
-            Outcome::Loss => Move::Rock,
-            Outcome::Draw => Move::Paper,
-            Outcome::Win => Move::Scissors,
-        },
-        Move::Scissors => match outcome {
-            Outcome::Loss => Move::Paper,
-            Outcome::Draw => Move::Scissors,
-            Outcome::Win => Move::Rock,
-        },
-    }
-}
-
-fn score(opponent: Move, player: Move) -> i64 {
-    let choice_score = player.score();
-    let win_score = match (opponent, player) {
-        (Move::Rock, Move::Paper) => 6,
-        (Move::Rock, Move::Scissors) => 0,
-        (Move::Paper, Move::Rock) => 0,
-        (Move::Paper, Move::Scissors) => 6,
-        (Move::Scissors, Move::Rock) => 6,
-        (Move::Scissors, Move::Paper) => 0,
-        _ => 3,
-    };
-    win_score + choice_score
+
+impl RuleSet {
+    fn new(moves: &[MoveSpec], beats: &[(&str, &str)]) -> Result<Self> {
+        let invalid = |message: String| -> Box<dyn std::error::Error> {
+            Box::new(SimpleError::new(format!("Invalid rule set: {}", message)))
+        };
+        let n = moves.len();
+        if n < 3 || n.is_multiple_of(2) {
+            return Err(invalid(format!("needs an odd number of moves, got {}", n)));
+        }
+        for (i, a) in moves.iter().enumerate() {
+            for b in &moves[i + 1..] {
+                if a.name == b.name || a.opponent == b.opponent || a.player == b.player {
+                    return Err(invalid(format!("'{}' and '{}' clash", a.name, b.name)));
+                }
+            }
+        }
+        let index = |name: &str| {
+            moves
+                .iter()
+                .position(|spec| spec.name == name)
+                .ok_or_else(|| invalid(format!("unknown move '{}'", name)))
+        };
+        let mut matrix = vec![vec![false; n]; n];
+        for (winner, loser) in beats {
+            let (winner, loser) = (index(winner)?, index(loser)?);
+            if winner == loser || matrix[loser][winner] {
+                return Err(invalid(format!(
+                    "'{}' and '{}' cannot beat each other",
+                    moves[winner].name, moves[loser].name
+                )));
+            }
+            matrix[winner][loser] = true;
+        }
+        for (i, spec) in moves.iter().enumerate() {
+            let wins = matrix[i].iter().filter(|beaten| **beaten).count();
+            if wins != (n - 1) / 2 {
+                return Err(invalid(format!(
+                    "'{}' beats {} moves, expected {}",
+                    spec.name,
+                    wins,
+                    (n - 1) / 2
+                )));
+            }
+        }
+        Ok(Self {
+            moves: moves.to_vec(),
+            beats: matrix,
+        })
+    }
+
+    fn builtin(name: &str) -> Result<Self> {
+        match name {
+            "rps" => Self::new(RPS_MOVES, RPS_BEATS),
+            "rpsls" => Self::new(RPSLS_MOVES, RPSLS_BEATS),
+            _ => Err(Box::new(SimpleError::new(format!(
+                "Unknown rule set '{}', expected one of: {}",
+                name,
+                BUILTIN_RULES.join(", ")
+            )))),
+        }
+    }
+
+    fn opponent_move(&self, c: char) -> Result<Move> {
+        self.moves
+            .iter()
+            .position(|spec| spec.opponent == c)
+            .map(Move)
+            .ok_or_else(|| Box::new(SimpleError::new(format!("Invalid move spec: '{}'", c))).into())
+    }
+
+    fn player_move(&self, c: char) -> Result<Move> {
+        self.moves
+            .iter()
+            .position(|spec| spec.player == c)
+            .map(Move)
+            .ok_or_else(|| Box::new(SimpleError::new(format!("Invalid move spec: '{}'", c))).into())
+    }
+
+    fn outcome(&self, opponent: Move, player: Move) -> Outcome {
+        if self.beats[player.0][opponent.0] {
+            Outcome::Win
+        } else if self.beats[opponent.0][player.0] {
+            Outcome::Loss
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    fn score(&self, opponent: Move, player: Move) -> i64 {
+        self.outcome(opponent, player).score() + self.moves[player.0].score
+    }
+
+    /// The move giving `outcome` against `opponent`. With more than three moves
+    /// several may qualify, in which case the highest scoring one is picked.
+    fn choose_move(&self, opponent: Move, outcome: Outcome) -> Move {
+        (0..self.moves.len())
+            .map(Move)
+            .filter(|player| self.outcome(opponent, *player) == outcome)
+            .max_by_key(|player| self.moves[player.0].score)
+            .unwrap()
+    }
 }
 
-impl Solution for Day02 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 02 - Part 1");
-        let input = std::fs::read_to_string("data/day02.txt")?;
-        let moves = input.split("\n").map(|line| {
-            let parts: Vec<&str> = line.split(" ").collect();
-            if parts.len() != 2 || parts[0].len() != 1 || parts[1].len() != 1 {
-                panic!("Invalid line in strategy guide: '{}'", line);
+fn rule_set(ctx: &Context) -> Result<RuleSet> {
+    RuleSet::builtin(&ctx.params.get("rules", "rps".to_owned())?)
+}
+
+fn parse_line(line: &str) -> Result<(char, char)> {
+    let parts: Vec<&str> = line.split(' ').collect();
+    if parts.len() != 2 || parts[0].chars().count() != 1 || parts[1].chars().count() != 1 {
+        return Err(Box::new(SimpleError::new(format!(
+            "Invalid line in strategy guide: '{}'",
+            line
+        ))));
+    }
+    Ok((
+        parts[0].chars().next().unwrap(),
+        parts[1].chars().next().unwrap(),
+    ))
+}
+
+/// One reading of the strategy guide's second column, with an entry per
+/// letter in `Decryption::letters`.
+#[derive(Debug, Clone)]
+enum Mapping {
+    Moves(Vec<Move>),
+    Outcomes(Vec<Outcome>),
+}
+
+/// Every injective mapping of `k` letters onto `n` values, in lexicographic order.
+fn arrangements(k: usize, n: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    let mut result = Vec::new();
+    for prefix in arrangements(k - 1, n) {
+        for value in (0..n).filter(|value| !prefix.contains(value)) {
+            let mut arrangement = prefix.clone();
+            arrangement.push(value);
+            result.push(arrangement);
+        }
+    }
+    result
+}
+
+struct Decryption<'a> {
+    rules: &'a RuleSet,
+    rounds: Vec<(Move, char)>,
+    /// The distinct letters used in the second column, sorted.
+    letters: Vec<char>,
+}
+
+impl<'a> Decryption<'a> {
+    fn new(rules: &'a RuleSet, input: &str) -> Result<Self> {
+        let mut rounds = Vec::new();
+        for line in input.lines() {
+            let (opponent, letter) = parse_line(line)?;
+            rounds.push((rules.opponent_move(opponent)?, letter));
+        }
+        let mut letters: Vec<char> = rounds.iter().map(|(_, letter)| *letter).collect();
+        letters.sort_unstable();
+        letters.dedup();
+        Ok(Self {
+            rules,
+            rounds,
+            letters,
+        })
+    }
+
+    /// Every assignment of the letters to distinct moves, followed by every
+    /// assignment to distinct outcomes if there are few enough letters.
+    fn mappings(&self) -> Result<Vec<Mapping>> {
+        let k = self.letters.len();
+        if k > self.rules.moves.len() {
+            let letters: Vec<String> = self.letters.iter().map(char::to_string).collect();
+            return Err(Box::new(SimpleError::new(format!(
+                "The second column uses {} letters ({}) but the rule set only has {} moves",
+                k,
+                letters.join(", "),
+                self.rules.moves.len()
+            ))));
+        }
+        let mut mappings: Vec<Mapping> = arrangements(k, self.rules.moves.len())
+            .into_iter()
+            .map(|moves| Mapping::Moves(moves.into_iter().map(Move).collect()))
+            .collect();
+        if k <= OUTCOMES.len() {
+            mappings.extend(arrangements(k, OUTCOMES.len()).into_iter().map(|outcomes| {
+                Mapping::Outcomes(outcomes.into_iter().map(|idx| OUTCOMES[idx]).collect())
+            }));
+        }
+        Ok(mappings)
+    }
+
+    fn describe(&self, mapping: &Mapping) -> String {
+        let values: Vec<String> = self
+            .letters
+            .iter()
+            .enumerate()
+            .map(|(idx, letter)| match mapping {
+                Mapping::Moves(moves) => {
+                    format!("{}={}", letter, self.rules.moves[moves[idx].0].name)
+                }
+                Mapping::Outcomes(outcomes) => format!("{}={}", letter, outcomes[idx].name()),
+            })
+            .collect();
+        let kind = match mapping {
+            Mapping::Moves(_) => "moves",
+            Mapping::Outcomes(_) => "outcomes",
+        };
+        format!("{:<8} {}", kind, values.join(" "))
+    }
+
+    /// The player's move in each round under `mapping`.
+    fn player_moves(&self, mapping: &Mapping) -> Vec<Move> {
+        self.rounds
+            .iter()
+            .map(|(opponent, letter)| {
+                let idx = self.letters.binary_search(letter).unwrap();
+                match mapping {
+                    Mapping::Moves(moves) => moves[idx],
+                    Mapping::Outcomes(outcomes) => self.rules.choose_move(*opponent, outcomes[idx]),
+                }
+            })
+            .collect()
+    }
+
+    fn total(&self, mapping: &Mapping) -> i64 {
+        self.rounds
+            .iter()
+            .zip(self.player_moves(mapping))
+            .map(|((opponent, _), player)| self.rules.score(*opponent, player))
+            .sum()
+    }
+
+    fn report(&self, selected: Option<usize>) -> Result<String> {
+        let mappings = self.mappings()?;
+        let totals: Vec<i64> = mappings.iter().map(|mapping| self.total(mapping)).collect();
+        let best = totals.iter().max().copied().unwrap_or(0);
+        let worst = totals.iter().min().copied().unwrap_or(0);
+
+        let mut output = String::new();
+        let _ = writeln!(output, "{:>3}  {:<40} {:>8}", "#", "Mapping", "Total");
+        for (idx, (mapping, total)) in mappings.iter().zip(&totals).enumerate() {
+            let mark = match *total {
+                total if total == best => "  best",
+                total if total == worst => "  worst",
+                _ => "",
+            };
+            let _ = writeln!(
+                output,
+                "{:>3}  {:<40} {:>8}{}",
+                idx + 1,
+                self.describe(mapping),
+                total,
+                mark
+            );
+        }
+
+        if let Some(selected) = selected {
+            let mapping = selected
+                .checked_sub(1)
+                .and_then(|idx| mappings.get(idx))
+                .ok_or_else(|| {
+                    SimpleError::new(format!(
+                        "Invalid mapping {}, expected 1..{}",
+                        selected,
+                        mappings.len()
+                    ))
+                })?;
+            let _ = writeln!(
+                output,
+                "\nRounds for mapping {}: {}",
+                selected,
+                self.describe(mapping)
+            );
+            let mut running = 0;
+            for (round, ((opponent, letter), player)) in self
+                .rounds
+                .iter()
+                .zip(self.player_moves(mapping))
+                .enumerate()
+            {
+                let score = self.rules.score(*opponent, player);
+                running += score;
+                let _ = writeln!(
+                    output,
+                    "{:>5}  {} {}  {} vs {}: {}, {} points, {} total",
+                    round + 1,
+                    self.rules.moves[opponent.0].opponent,
+                    letter,
+                    self.rules.moves[player.0].name,
+                    self.rules.moves[opponent.0].name,
+                    self.rules.outcome(*opponent, player).name(),
+                    score,
+                    running
+                );
             }
-            (
-                Move::from_char(parts[0].chars().next().unwrap()),
-                Move::from_char(parts[1].chars().next().unwrap()),
-            )
-        });
-        let total_score: i64 = moves
-            .map(|(opponent, player)| score(opponent, player))
-            .sum();
-        println!("Total score: {}", total_score);
-        Ok(())
-    }
-
-    fn part2(&self) -> Result<()> {
-        println!("Day 02 - Part 1");
-        let input = std::fs::read_to_string("data/day02.txt")?;
-        let rounds = input.split("\n").map(|line| {
-            let parts: Vec<&str> = line.split(" ").collect();
-            if parts.len() != 2 || parts[0].len() != 1 || parts[1].len() != 1 {
-                panic!("Invalid line in strategy guide: '{}'", line);
+        }
+        Ok(output.trim_end().to_owned())
+    }
+}
+
+/// Seeded splitmix64, so simulations are reproducible without a dependency on
+/// a random number crate.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// A player in the tournament simulator.
+trait Strategy {
+    /// The move for the next round, given both players' moves so far.
+    fn next_move(&mut self, rules: &RuleSet, own: &[Move], opponent: &[Move]) -> Move;
+}
+
+/// Names accepted by the `strategies` parameter.
+const STRATEGIES: &[&str] = &[
+    "fixed",
+    "cyclic",
+    "random",
+    "frequency",
+    "markov",
+    "copy-last",
+    "guide",
+];
+
+/// The move that beats `predicted`.
+fn counter(rules: &RuleSet, predicted: Move) -> Move {
+    rules.choose_move(predicted, Outcome::Win)
+}
+
+/// Index of the largest count, preferring the earliest on ties.
+fn most_common(counts: &[usize]) -> Option<Move> {
+    let (idx, count) = counts
+        .iter()
+        .enumerate()
+        .max_by_key(|(idx, count)| (**count, std::cmp::Reverse(*idx)))?;
+    (*count > 0).then_some(Move(idx))
+}
+
+struct Fixed(Move);
+
+impl Strategy for Fixed {
+    fn next_move(&mut self, _rules: &RuleSet, _own: &[Move], _opponent: &[Move]) -> Move {
+        self.0
+    }
+}
+
+struct Cyclic;
+
+impl Strategy for Cyclic {
+    fn next_move(&mut self, rules: &RuleSet, own: &[Move], _opponent: &[Move]) -> Move {
+        Move(own.len() % rules.moves.len())
+    }
+}
+
+struct Random(SplitMix64);
+
+impl Strategy for Random {
+    fn next_move(&mut self, rules: &RuleSet, _own: &[Move], _opponent: &[Move]) -> Move {
+        Move(self.0.below(rules.moves.len()))
+    }
+}
+
+/// Counters the opponent's most frequent move so far.
+struct Frequency(Vec<usize>);
+
+impl Strategy for Frequency {
+    fn next_move(&mut self, rules: &RuleSet, _own: &[Move], opponent: &[Move]) -> Move {
+        if let Some(last) = opponent.last() {
+            self.0[last.0] += 1;
+        }
+        most_common(&self.0).map_or(Move(0), |predicted| counter(rules, predicted))
+    }
+}
+
+/// Counters the move the opponent most often played after their previous move,
+/// falling back to their most frequent move while that is unknown.
+struct Markov {
+    transitions: Vec<Vec<usize>>,
+    frequency: Vec<usize>,
+}
+
+impl Strategy for Markov {
+    fn next_move(&mut self, rules: &RuleSet, _own: &[Move], opponent: &[Move]) -> Move {
+        if let [.., previous, last] = opponent {
+            self.transitions[previous.0][last.0] += 1;
+        }
+        if let Some(last) = opponent.last() {
+            self.frequency[last.0] += 1;
+        }
+        let predicted = opponent
+            .last()
+            .and_then(|last| most_common(&self.transitions[last.0]))
+            .or_else(|| most_common(&self.frequency));
+        predicted.map_or(Move(0), |predicted| counter(rules, predicted))
+    }
+}
+
+struct CopyLast;
+
+impl Strategy for CopyLast {
+    fn next_move(&mut self, _rules: &RuleSet, _own: &[Move], opponent: &[Move]) -> Move {
+        opponent.last().copied().unwrap_or(Move(0))
+    }
+}
+
+/// Replays the opponent column of the strategy guide, wrapping around.
+struct Guide(Vec<Move>);
+
+impl Strategy for Guide {
+    fn next_move(&mut self, _rules: &RuleSet, own: &[Move], _opponent: &[Move]) -> Move {
+        self.0[own.len() % self.0.len()]
+    }
+}
+
+struct Tournament<'a> {
+    rules: &'a RuleSet,
+    strategies: Vec<&'a str>,
+    guide: Vec<Move>,
+    fixed: Move,
+    rounds: usize,
+    seed: u64,
+}
+
+/// Totals for one side of a match.
+#[derive(Debug, Default, Clone, Copy)]
+struct Tally {
+    score: i64,
+    wins: usize,
+    draws: usize,
+    losses: usize,
+}
+
+impl Tally {
+    fn add(&mut self, other: &Tally) {
+        self.score += other.score;
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    fn win_rate(&self) -> f64 {
+        let rounds = self.wins + self.draws + self.losses;
+        if rounds == 0 {
+            0.0
+        } else {
+            100.0 * self.wins as f64 / rounds as f64
+        }
+    }
+}
+
+impl<'a> Tournament<'a> {
+    fn new(rules: &'a RuleSet, input: &str, ctx: &'a Context) -> Result<Self> {
+        let mut guide = Vec::new();
+        for line in input.lines() {
+            guide.push(rules.opponent_move(parse_line(line)?.0)?);
+        }
+        let fixed_name: String = ctx.params.get("fixed", rules.moves[0].name.to_owned())?;
+        let fixed = rules
+            .moves
+            .iter()
+            .position(|spec| spec.name.eq_ignore_ascii_case(&fixed_name))
+            .map(Move)
+            .ok_or_else(|| SimpleError::new(format!("Unknown move '{}'", fixed_name)))?;
+        let strategies = match ctx.params.get_opt::<String>("strategies")? {
+            Some(names) => names
+                .split(',')
+                .map(|name| {
+                    let name = name.trim();
+                    STRATEGIES
+                        .iter()
+                        .find(|known| **known == name)
+                        .copied()
+                        .ok_or_else(|| {
+                            SimpleError::new(format!(
+                                "Unknown strategy '{}', expected one of: {}",
+                                name,
+                                STRATEGIES.join(", ")
+                            ))
+                        })
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+            None => STRATEGIES
+                .iter()
+                .copied()
+                .filter(|name| *name != "guide" || !guide.is_empty())
+                .collect(),
+        };
+        if strategies.contains(&"guide") && guide.is_empty() {
+            return Err(Box::new(SimpleError::new(
+                "The guide strategy needs a non-empty strategy guide",
+            )));
+        }
+        if strategies.len() < 2 {
+            return Err(Box::new(SimpleError::new(
+                "A tournament needs at least two strategies",
+            )));
+        }
+        Ok(Self {
+            rules,
+            strategies,
+            guide,
+            fixed,
+            rounds: ctx.params.get("rounds", 1000)?,
+            seed: ctx.params.get("seed", 0)?,
+        })
+    }
+
+    /// A fresh instance of strategy `idx`; `stream` keeps random players in
+    /// different matches from drawing the same sequence.
+    fn strategy(&self, idx: usize, stream: u64) -> Box<dyn Strategy> {
+        let moves = self.rules.moves.len();
+        match self.strategies[idx] {
+            "fixed" => Box::new(Fixed(self.fixed)),
+            "cyclic" => Box::new(Cyclic),
+            "random" => {
+                let mut seeder = SplitMix64(self.seed ^ stream.wrapping_mul(0x2545_f491_4f6c_dd1d));
+                Box::new(Random(SplitMix64(seeder.next())))
             }
+            "frequency" => Box::new(Frequency(vec![0; moves])),
+            "markov" => Box::new(Markov {
+                transitions: vec![vec![0; moves]; moves],
+                frequency: vec![0; moves],
+            }),
+            "copy-last" => Box::new(CopyLast),
+            "guide" => Box::new(Guide(self.guide.clone())),
+            name => unreachable!("unknown strategy {}", name),
+        }
+    }
+
+    fn play(
+        &self,
+        first: usize,
+        second: usize,
+        stream: u64,
+        cancel: &CancellationToken,
+    ) -> Result<(Tally, Tally)> {
+        let mut players = [
+            self.strategy(first, 2 * stream),
+            self.strategy(second, 2 * stream + 1),
+        ];
+        let mut history: [Vec<Move>; 2] = [Vec::new(), Vec::new()];
+        let mut tallies = [Tally::default(); 2];
+        for _ in 0..self.rounds {
+            cancel.check()?;
+            let moves = [
+                players[0].next_move(self.rules, &history[0], &history[1]),
+                players[1].next_move(self.rules, &history[1], &history[0]),
+            ];
+            for side in 0..2 {
+                let (own, opponent) = (moves[side], moves[1 - side]);
+                let tally = &mut tallies[side];
+                tally.score += self.rules.score(opponent, own);
+                match self.rules.outcome(opponent, own) {
+                    Outcome::Win => tally.wins += 1,
+                    Outcome::Draw => tally.draws += 1,
+                    Outcome::Loss => tally.losses += 1,
+                }
+            }
+            history[0].push(moves[0]);
+            history[1].push(moves[1]);
+        }
+        Ok((tallies[0], tallies[1]))
+    }
+
+    /// Plays every pair of strategies once and ranks them by matches won, then
+    /// by total score.
+    fn run(&self, cancel: &CancellationToken) -> Result<String> {
+        let n = self.strategies.len();
+        let mut totals = vec![Tally::default(); n];
+        let mut match_records = vec![(0, 0, 0); n];
+        let mut output = String::new();
+        let _ = writeln!(
+            output,
+            "{} strategies, {} rounds per match, seed {}\n",
+            n, self.rounds, self.seed
+        );
+        let mut stream = 0;
+        for first in 0..n {
+            for second in first + 1..n {
+                let (a, b) = self.play(first, second, stream, cancel)?;
+                stream += 1;
+                totals[first].add(&a);
+                totals[second].add(&b);
+                let (winner, loser) = match a.score.cmp(&b.score) {
+                    std::cmp::Ordering::Greater => (first, second),
+                    std::cmp::Ordering::Less => (second, first),
+                    std::cmp::Ordering::Equal => {
+                        match_records[first].1 += 1;
+                        match_records[second].1 += 1;
+                        (n, n)
+                    }
+                };
+                if winner < n {
+                    match_records[winner].0 += 1;
+                    match_records[loser].2 += 1;
+                }
+                let _ = writeln!(
+                    output,
+                    "{:>10} vs {:<10} {:>7} - {:<7} (won {} / {} / {} drawn)",
+                    self.strategies[first],
+                    self.strategies[second],
+                    a.score,
+                    b.score,
+                    a.wins,
+                    b.wins,
+                    a.draws
+                );
+            }
+        }
+
+        let mut ranking: Vec<usize> = (0..n).collect();
+        ranking.sort_by_key(|idx| {
             (
-                Move::from_char(parts[0].chars().next().unwrap()),
-                Outcome::from_char(parts[1].chars().next().unwrap()),
+                std::cmp::Reverse(match_records[*idx].0),
+                std::cmp::Reverse(totals[*idx].score),
+                *idx,
             )
         });
-        let total_score: i64 = rounds
-            .map(|(opponent, outcome)| {
-                let player_move = choose_move(opponent, outcome);
-                score(opponent, player_move)
-            })
-            .sum();
-        println!("Total score: {}", total_score);
-        Ok(())
+        let _ = writeln!(
+            output,
+            "\n{:>4}  {:<10} {:>7} {:>9} {:>9}",
+            "Rank", "Strategy", "W-D-L", "Win rate", "Score"
+        );
+        for (rank, idx) in ranking.iter().enumerate() {
+            let (won, drawn, lost) = match_records[*idx];
+            let _ = writeln!(
+                output,
+                "{:>4}  {:<10} {:>7} {:>8.1}% {:>9}",
+                rank + 1,
+                self.strategies[*idx],
+                format!("{}-{}-{}", won, drawn, lost),
+                totals[*idx].win_rate(),
+                totals[*idx].score
+            );
+        }
+        Ok(output.trim_end().to_owned())
+    }
+}
+
+impl Solution for Day02 {
+    fn part1(&self, input: &str, ctx: &Context) -> Result<String> {
+        let rules = rule_set(ctx)?;
+        let mut total_score = 0;
+        for line in input.lines() {
+            let (opponent, player) = parse_line(line)?;
+            total_score += rules.score(rules.opponent_move(opponent)?, rules.player_move(player)?);
+        }
+        Ok(total_score.to_string())
+    }
+
+    fn part2(&self, input: &str, ctx: &Context) -> Result<String> {
+        let rules = rule_set(ctx)?;
+        let mut total_score = 0;
+        for line in input.lines() {
+            let (opponent, outcome) = parse_line(line)?;
+            let opponent = rules.opponent_move(opponent)?;
+            let player_move = rules.choose_move(opponent, Outcome::from_char(outcome)?);
+            total_score += rules.score(opponent, player_move);
+        }
+        Ok(total_score.to_string())
+    }
+
+    fn modes(&self) -> &'static [&'static str] {
+        &["decrypt", "tournament"]
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["rules", "fixed", "strategies", "rounds", "seed", "mapping"]
+    }
+
+    fn run_mode(&self, mode: &str, input: &str, ctx: &Context) -> Result<String> {
+        match mode {
+            "decrypt" => {
+                let rules = rule_set(ctx)?;
+                Decryption::new(&rules, input)?.report(ctx.params.get_opt("mapping")?)
+            }
+            "tournament" => {
+                let rules = rule_set(ctx)?;
+                Tournament::new(&rules, input, ctx)?.run(&ctx.cancel)
+            }
+            _ => Err(unsupported_mode(mode, self.modes())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOVE_NAMES: [&str; 5] = ["Rock", "Paper", "Scissors", "Lizard", "Spock"];
+
+    fn named(rules: &RuleSet, name: &str) -> Move {
+        Move(
+            rules
+                .moves
+                .iter()
+                .position(|spec| spec.name == name)
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn rejects_even_rule_sets() {
+        let beats = [("Rock", "Paper"), ("Paper", "Scissors")];
+        assert!(RuleSet::new(&RPSLS_MOVES[..4], &beats).is_err());
+        assert!(RuleSet::new(&RPS_MOVES[..2], &[("Rock", "Paper")]).is_err());
+    }
+
+    #[test]
+    fn rejects_empty_rule_sets() {
+        assert!(RuleSet::new(&[], &[]).is_err());
+    }
+
+    #[test]
+    fn rejects_asymmetric_beats() {
+        // A pair that beats each other.
+        let mutual = [
+            ("Rock", "Scissors"),
+            ("Scissors", "Rock"),
+            ("Paper", "Rock"),
+        ];
+        assert!(RuleSet::new(RPS_MOVES, &mutual).is_err());
+        // Rock beats everything, Paper nothing.
+        let dominant = [
+            ("Rock", "Scissors"),
+            ("Rock", "Paper"),
+            ("Scissors", "Paper"),
+        ];
+        assert!(RuleSet::new(RPS_MOVES, &dominant).is_err());
+        // Nobody beats Rock.
+        assert!(RuleSet::new(RPS_MOVES, &RPS_BEATS[..2]).is_err());
+        assert!(RuleSet::new(RPS_MOVES, &[("Rock", "Rock")]).is_err());
+        assert!(RuleSet::new(RPS_MOVES, RPS_BEATS).is_ok());
+    }
+
+    #[test]
+    fn scores_every_rpsls_pairing() {
+        let rules = RuleSet::builtin("rpsls").unwrap();
+        // Rows are the player's move, columns the opponent's, both in
+        // MOVE_NAMES order.
+        let expected = ["DLWWL", "WDLLW", "LWDWL", "LWLDW", "WLWLD"];
+        for (p, row) in expected.iter().enumerate() {
+            for (o, outcome) in row.chars().enumerate() {
+                let player = named(&rules, MOVE_NAMES[p]);
+                let opponent = named(&rules, MOVE_NAMES[o]);
+                let (outcome, points) = match outcome {
+                    'W' => (Outcome::Win, 6),
+                    'D' => (Outcome::Draw, 3),
+                    _ => (Outcome::Loss, 0),
+                };
+                assert_eq!(rules.outcome(opponent, player), outcome);
+                assert_eq!(rules.score(opponent, player), points + p as i64 + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn chooses_highest_scoring_rpsls_move() {
+        let rules = RuleSet::builtin("rpsls").unwrap();
+        // For each opponent move: the chosen loss, draw and win.
+        let expected = [
+            ("Rock", ["Lizard", "Rock", "Spock"]),
+            ("Paper", ["Spock", "Paper", "Lizard"]),
+            ("Scissors", ["Lizard", "Scissors", "Spock"]),
+            ("Lizard", ["Spock", "Lizard", "Scissors"]),
+            ("Spock", ["Scissors", "Spock", "Lizard"]),
+        ];
+        for (opponent, choices) in expected {
+            let opponent = named(&rules, opponent);
+            for (outcome, choice) in OUTCOMES.iter().zip(choices) {
+                let chosen = rules.choose_move(opponent, *outcome);
+                assert_eq!(chosen, named(&rules, choice));
+                assert_eq!(rules.outcome(opponent, chosen), *outcome);
+                for player in MOVE_NAMES.iter().map(|name| named(&rules, name)) {
+                    if rules.outcome(opponent, player) == *outcome {
+                        assert!(rules.score(opponent, player) <= rules.score(opponent, chosen));
+                    }
+                }
+            }
+        }
+    }
+
+    const EXAMPLE: &str = "A Y\nB X\nC Z";
+
+    #[test]
+    fn enumerates_mappings() {
+        assert_eq!(arrangements(0, 3), vec![Vec::<usize>::new()]);
+        assert_eq!(arrangements(2, 3).len(), 6);
+        assert_eq!(arrangements(2, 3)[..2], [vec![0, 1], vec![0, 2]]);
+        assert!(arrangements(4, 3).is_empty());
+
+        let rps = RuleSet::builtin("rps").unwrap();
+        let mappings = Decryption::new(&rps, EXAMPLE).unwrap().mappings().unwrap();
+        assert_eq!(mappings.len(), 12);
+        // Five letters can only be moves in RPSLS: 5! readings, no outcomes.
+        let rpsls = RuleSet::builtin("rpsls").unwrap();
+        let input = "A V\nA W\nA X\nA Y\nA Z";
+        let mappings = Decryption::new(&rpsls, input).unwrap().mappings().unwrap();
+        assert_eq!(mappings.len(), 120);
+        assert!(mappings
+            .iter()
+            .all(|mapping| matches!(mapping, Mapping::Moves(_))));
+    }
+
+    #[test]
+    fn known_mappings_give_puzzle_totals() {
+        let rules = RuleSet::builtin("rps").unwrap();
+        let decryption = Decryption::new(&rules, EXAMPLE).unwrap();
+        let mappings = decryption.mappings().unwrap();
+        let totals: Vec<i64> = mappings.iter().map(|m| decryption.total(m)).collect();
+        // X=Rock Y=Paper Z=Scissors is part 1, X=Loss Y=Draw Z=Win is part 2.
+        assert_eq!(totals[0], 15);
+        assert_eq!(totals[6], 12);
+        assert!(decryption
+            .describe(&mappings[6])
+            .ends_with("X=Loss Y=Draw Z=Win"));
+        assert_eq!(totals.iter().max(), Some(&24));
+        assert!(decryption.report(Some(13)).is_err());
+    }
+
+    #[test]
+    fn too_many_letters_is_an_error() {
+        let rules = RuleSet::builtin("rps").unwrap();
+        let decryption = Decryption::new(&rules, "A W\nA X\nA Y\nA Z").unwrap();
+        let error = decryption.mappings().unwrap_err().to_string();
+        assert!(error.contains("W, X, Y, Z"), "{}", error);
+    }
+
+    fn tournament_context(seed: u64) -> Context {
+        let mut ctx = Context::default();
+        ctx.params.insert("rounds", "200");
+        ctx.params.insert("seed", &seed.to_string());
+        ctx
+    }
+
+    #[test]
+    fn tournament_is_reproducible() {
+        let rules = RuleSet::builtin("rpsls").unwrap();
+        let ctx = tournament_context(42);
+        let first = Tournament::new(&rules, EXAMPLE, &ctx)
+            .unwrap()
+            .run(&ctx.cancel)
+            .unwrap();
+        let second = Tournament::new(&rules, EXAMPLE, &ctx)
+            .unwrap()
+            .run(&ctx.cancel)
+            .unwrap();
+        assert_eq!(first, second);
+        assert!(first.contains("random"));
+        assert!(first.contains("guide"));
+    }
+
+    #[test]
+    fn tournament_stops_when_cancelled() {
+        let rules = RuleSet::builtin("rps").unwrap();
+        let ctx = tournament_context(0);
+        ctx.cancel.cancel();
+        let tournament = Tournament::new(&rules, EXAMPLE, &ctx).unwrap();
+        assert!(tournament.play(0, 1, 0, &ctx.cancel).is_err());
+        assert!(tournament.run(&ctx.cancel).is_err());
     }
 }
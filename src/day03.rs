@@ -1,17 +1,60 @@
 use crate::solution::*;
-use std::collections::HashSet;
+use simple_error::SimpleError;
 use std::error::Error;
-use std::fmt::Display;
+use std::fmt::{Display, Write};
 
 #[derive(Debug)]
 enum ProcessError {
     InvalidItem(char),
-    NoItemInBoth,
+    /// The rucksack on this (1-based) line can't be split into equal compartments.
+    UnevenCompartments {
+        line: usize,
+        items: usize,
+        compartments: usize,
+    },
+    /// Nothing is shared by all the lists starting at this line.
+    NoItemInBoth(usize),
+    /// More than one item is shared by all the lists starting at this line.
+    MultipleShared(usize, ItemSet),
+    /// The input ends with a group of fewer than `expected` rucksacks.
+    IncompleteGroup {
+        line: usize,
+        size: usize,
+        expected: usize,
+    },
 }
 
 impl Display for ProcessError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:#?}", self)
+        match self {
+            ProcessError::InvalidItem(c) => write!(f, "Invalid item: '{}'", c),
+            ProcessError::UnevenCompartments {
+                line,
+                items,
+                compartments,
+            } => write!(
+                f,
+                "Line {}: {} items can't be split into {} equal compartments",
+                line, items, compartments
+            ),
+            ProcessError::NoItemInBoth(line) => {
+                write!(f, "Line {}: no item shared by all lists", line)
+            }
+            ProcessError::MultipleShared(line, items) => write!(
+                f,
+                "Line {}: expected one shared item, found {}",
+                line, items
+            ),
+            ProcessError::IncompleteGroup {
+                line,
+                size,
+                expected,
+            } => write!(
+                f,
+                "Line {}: incomplete group of {} rucksacks, expected {}",
+                line, size, expected
+            ),
+        }
     }
 }
 
@@ -29,56 +72,444 @@ fn priority(c: char) -> Result<i8> {
     }
 }
 
-fn common_item(lists: &[&[char]]) -> Result<char> {
-    let mut sets: Vec<HashSet<char>> = Vec::new();
-    for list in lists {
-        let mut set = HashSet::new();
-        set.extend(list.iter());
-        sets.push(set);
+/// The item with the given priority.
+fn item(priority: i8) -> char {
+    match priority {
+        1..=26 => (b'a' + (priority - 1) as u8) as char,
+        _ => (b'A' + (priority - 27) as u8) as char,
     }
+}
 
-    'outer: for c in sets.first().unwrap().iter() {
-        for set in sets.iter() {
-            if !set.contains(c) {
-                continue 'outer;
-            }
+/// A set of item types, one bit per priority (bit 0 is `a`, bit 51 is `Z`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    fn from_items(items: &[char]) -> Result<Self> {
+        let mut set = ItemSet::default();
+        for c in items {
+            set = set.union(ItemSet(1 << (priority(*c)? - 1)));
         }
-        return Ok(*c);
+        Ok(set)
+    }
+
+    fn intersection(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 & other.0)
+    }
+
+    fn union(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 | other.0)
+    }
+
+    fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Priorities of the items in the set, in ascending order.
+    fn priorities(self) -> impl Iterator<Item = i8> {
+        (0..52)
+            .filter(move |bit| self.0 & (1 << bit) != 0)
+            .map(|bit| bit + 1)
+    }
+}
+
+impl Display for ItemSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items: Vec<String> = self.priorities().map(|p| item(p).to_string()).collect();
+        write!(f, "{{{}}}", items.join(", "))
+    }
+}
+
+/// The single item shared by every set. `line` is where the lists start, for
+/// error messages.
+fn common_item(sets: &[ItemSet], line: usize) -> Result<i8> {
+    let shared = sets
+        .iter()
+        .fold(ItemSet(u64::MAX), |shared, set| shared.intersection(*set));
+    if shared.is_empty() {
+        Err(Box::new(ProcessError::NoItemInBoth(line)))
+    } else if shared.len() > 1 {
+        Err(Box::new(ProcessError::MultipleShared(line, shared)))
+    } else {
+        Ok(shared.priorities().next().unwrap())
     }
-    Err(Box::new(ProcessError::NoItemInBoth))
 }
 
-fn process_line(line: &str) -> Result<i64> {
+/// The items of each compartment of the rucksack on `line_number`.
+fn split_compartments(line: &str, line_number: usize, count: usize) -> Result<Vec<Vec<char>>> {
     let elements: Vec<char> = line.chars().collect();
-    let first_compartment = &elements[0..elements.len() / 2];
-    let second_compartment = &elements[elements.len() / 2..];
-    let common = common_item(&[first_compartment, second_compartment])?;
-    priority(common).map(|x| x as i64)
+    if !elements.len().is_multiple_of(count) {
+        return Err(Box::new(ProcessError::UnevenCompartments {
+            line: line_number,
+            items: elements.len(),
+            compartments: count,
+        }));
+    }
+    if elements.is_empty() {
+        return Ok(vec![Vec::new(); count]);
+    }
+    Ok(elements
+        .chunks(elements.len() / count)
+        .map(|chunk| chunk.to_vec())
+        .collect())
+}
+
+fn compartments(line: &str, line_number: usize, count: usize) -> Result<Vec<ItemSet>> {
+    split_compartments(line, line_number, count)?
+        .iter()
+        .map(|items| ItemSet::from_items(items))
+        .collect()
+}
+
+/// Moving `count` copies of `item` between two compartments of a rucksack.
+struct Move {
+    item: char,
+    count: usize,
+    from: usize,
+    to: usize,
+}
+
+/// The fewest moves that leave every item type in a single compartment: each
+/// type is gathered in the compartment already holding most of its copies
+/// (the first such one on ties). Compartment sizes are not kept equal.
+fn reorganise(compartments: &[Vec<char>]) -> Result<Vec<Move>> {
+    // counts[p][c]: copies of the item with priority p + 1 in compartment c.
+    let mut counts = vec![vec![0usize; compartments.len()]; 52];
+    for (idx, items) in compartments.iter().enumerate() {
+        for c in items {
+            counts[(priority(*c)? - 1) as usize][idx] += 1;
+        }
+    }
+    let mut moves = Vec::new();
+    for (p, per_compartment) in counts.iter().enumerate() {
+        let holders: Vec<usize> = (0..compartments.len())
+            .filter(|idx| per_compartment[*idx] > 0)
+            .collect();
+        if holders.len() < 2 {
+            continue;
+        }
+        let keeper = *holders
+            .iter()
+            .max_by_key(|idx| (per_compartment[**idx], std::cmp::Reverse(**idx)))
+            .unwrap();
+        for from in holders.into_iter().filter(|idx| *idx != keeper) {
+            moves.push(Move {
+                item: item(p as i8 + 1),
+                count: per_compartment[from],
+                from,
+                to: keeper,
+            });
+        }
+    }
+    Ok(moves)
+}
+
+fn reorganisation_report(
+    input: &str,
+    compartment_count: usize,
+    group_size: usize,
+) -> Result<String> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut output = String::new();
+    let (mut total_moves, mut total_cost, mut to_fix) = (0, 0, 0);
+    for (idx, line) in lines.iter().enumerate() {
+        let moves = reorganise(&split_compartments(line, idx + 1, compartment_count)?)?;
+        if moves.is_empty() {
+            continue;
+        }
+        to_fix += 1;
+        let mut fixes = Vec::new();
+        let mut cost = 0;
+        for fix in &moves {
+            let fix_cost = fix.count as i64 * priority(fix.item)? as i64;
+            cost += fix_cost;
+            total_moves += fix.count;
+            fixes.push(format!(
+                "{}x '{}' from compartment {} to {} (cost {})",
+                fix.count,
+                fix.item,
+                fix.from + 1,
+                fix.to + 1,
+                fix_cost
+            ));
+        }
+        total_cost += cost;
+        let _ = writeln!(
+            output,
+            "Rucksack {}: {} (total cost {})",
+            idx + 1,
+            fixes.join(", "),
+            cost
+        );
+    }
+    let _ = writeln!(
+        output,
+        "{} of {} rucksacks need fixing: {} item moves, priority cost {}",
+        to_fix,
+        lines.len(),
+        total_moves,
+        total_cost
+    );
+
+    let _ = writeln!(output, "\nBadges:");
+    let mut ambiguous = 0;
+    for (idx, group) in lines.chunks(group_size).enumerate() {
+        let first = idx * group_size + 1;
+        if group.len() < group_size {
+            return Err(Box::new(ProcessError::IncompleteGroup {
+                line: first,
+                size: group.len(),
+                expected: group_size,
+            }));
+        }
+        let mut shared = ItemSet(u64::MAX);
+        for rucksack in group {
+            shared =
+                shared.intersection(ItemSet::from_items(&rucksack.chars().collect::<Vec<_>>())?);
+        }
+        if shared.len() != 1 {
+            ambiguous += 1;
+            let _ = writeln!(
+                output,
+                "Group {} (lines {}-{}): {}",
+                idx + 1,
+                first,
+                first + group_size - 1,
+                if shared.is_empty() {
+                    "no candidate".to_owned()
+                } else {
+                    format!("ambiguous between {}", shared)
+                }
+            );
+        }
+    }
+    let _ = writeln!(
+        output,
+        "{} of {} groups without a unique badge",
+        ambiguous,
+        lines.len() / group_size
+    );
+    Ok(output.trim_end().to_owned())
+}
+
+fn positive_param(ctx: &Context, key: &str, default: usize) -> Result<usize> {
+    match ctx.params.get(key, default)? {
+        0 => Err(Box::new(SimpleError::new(format!(
+            "Parameter '{}' must be at least 1",
+            key
+        )))),
+        value => Ok(value),
+    }
 }
 
 impl Solution for Day03 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 03 - Part 1");
-        let input = std::fs::read_to_string("data/day03.txt")?;
-        let processed: Result<Vec<i64>> = input.lines().map(process_line).collect();
-        let priority_sum: i64 = processed?.iter().sum();
-        println!("Priority sum: {}", priority_sum);
-
-        Ok(())
-    }
-    fn part2(&self) -> Result<()> {
-        println!("Day 03 - Part 2");
-        let input = std::fs::read_to_string("data/day03.txt")?;
+    fn part1(&self, input: &str, ctx: &Context) -> Result<String> {
+        let count = positive_param(ctx, "compartments", 2)?;
+        let mut priority_sum: i64 = 0;
+        for (idx, line) in input.lines().enumerate() {
+            let sets = compartments(line, idx + 1, count)?;
+            priority_sum += common_item(&sets, idx + 1)? as i64;
+        }
+        Ok(priority_sum.to_string())
+    }
+    fn part2(&self, input: &str, ctx: &Context) -> Result<String> {
+        let group_size = positive_param(ctx, "group_size", 3)?;
         let lines: Vec<&str> = input.lines().collect();
         let mut priority_sum: i64 = 0;
-        for i in 0..lines.len() / 3 {
-            let l0: Vec<char> = lines[3 * i + 0].chars().collect();
-            let l1: Vec<char> = lines[3 * i + 1].chars().collect();
-            let l2: Vec<char> = lines[3 * i + 2].chars().collect();
-            let common = common_item(&[&l0, &l1, &l2])?;
-            priority_sum += priority(common)? as i64;
+        for (idx, group) in lines.chunks(group_size).enumerate() {
+            let line = idx * group_size + 1;
+            if group.len() < group_size {
+                return Err(Box::new(ProcessError::IncompleteGroup {
+                    line,
+                    size: group.len(),
+                    expected: group_size,
+                }));
+            }
+            let sets: Result<Vec<ItemSet>> = group
+                .iter()
+                .map(|rucksack| ItemSet::from_items(&rucksack.chars().collect::<Vec<_>>()))
+                .collect();
+            priority_sum += common_item(&sets?, line)? as i64;
         }
-        println!("Priority sum: {}", priority_sum);
-        Ok(())
+        Ok(priority_sum.to_string())
+    }
+
+    fn modes(&self) -> &'static [&'static str] {
+        &["reorganise"]
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["compartments", "group_size"]
+    }
+
+    fn run_mode(&self, mode: &str, input: &str, ctx: &Context) -> Result<String> {
+        match mode {
+            "reorganise" => reorganisation_report(
+                input,
+                positive_param(ctx, "compartments", 2)?,
+                positive_param(ctx, "group_size", 3)?,
+            ),
+            _ => Err(unsupported_mode(mode, self.modes())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
+
+    fn error(result: Result<String>) -> ProcessError {
+        *result.unwrap_err().downcast::<ProcessError>().unwrap()
+    }
+
+    fn set(items: &str) -> ItemSet {
+        ItemSet::from_items(&items.chars().collect::<Vec<_>>()).unwrap()
+    }
+
+    fn with_param(key: &str, value: &str) -> Context {
+        let mut ctx = Context::default();
+        ctx.params.insert(key, value);
+        ctx
+    }
+
+    #[test]
+    fn example() {
+        let ctx = Context::default();
+        assert_eq!(Day03 {}.part1(EXAMPLE, &ctx).unwrap(), "157");
+        assert_eq!(Day03 {}.part2(EXAMPLE, &ctx).unwrap(), "70");
+    }
+
+    #[test]
+    fn item_sets() {
+        let first = set("abZab");
+        assert_eq!(first, ItemSet(1 | 1 << 1 | 1 << 51));
+        assert_eq!(first.len(), 3);
+        assert!(!first.is_empty());
+        assert!(set("").is_empty());
+        assert_eq!(first.priorities().collect::<Vec<_>>(), vec![1, 2, 52]);
+        assert_eq!(first.to_string(), "{a, b, Z}");
+        assert_eq!(first.intersection(set("bZq")), set("Zb"));
+        assert_eq!(first.intersection(set("xyz")), ItemSet::default());
+        assert_eq!(set("aZ").union(set("ba")), first);
+        assert!(matches!(
+            ItemSet::from_items(&['a', '1']).unwrap_err().downcast_ref(),
+            Some(ProcessError::InvalidItem('1'))
+        ));
+    }
+
+    #[test]
+    fn multiple_shared_items() {
+        assert!(matches!(
+            error(Day03 {}.part1("abcdabce\nabab", &Context::default())),
+            ProcessError::MultipleShared(1, shared) if shared == set("abc")
+        ));
+        assert!(matches!(
+            error(Day03 {}.part1("aa\nabab", &Context::default())),
+            ProcessError::MultipleShared(2, shared) if shared == set("ab")
+        ));
+    }
+
+    #[test]
+    fn incomplete_group() {
+        let input = EXAMPLE.lines().take(5).collect::<Vec<_>>().join("\n");
+        assert!(matches!(
+            error(Day03 {}.part2(&input, &Context::default())),
+            ProcessError::IncompleteGroup {
+                line: 4,
+                size: 2,
+                expected: 3
+            }
+        ));
+        assert!(matches!(
+            error(Day03 {}.part2("ab\nac\nad\nae\nxa\nxb", &with_param("group_size", "4"))),
+            ProcessError::IncompleteGroup {
+                line: 5,
+                size: 2,
+                expected: 4
+            }
+        ));
+    }
+
+    #[test]
+    fn uneven_compartments() {
+        assert!(matches!(
+            error(Day03 {}.part1("aa\nabcab", &Context::default())),
+            ProcessError::UnevenCompartments {
+                line: 2,
+                items: 5,
+                compartments: 2
+            }
+        ));
+        assert!(matches!(
+            error(Day03 {}.part1(EXAMPLE, &with_param("compartments", "5"))),
+            ProcessError::UnevenCompartments {
+                line: 1,
+                items: 24,
+                compartments: 5
+            }
+        ));
+    }
+
+    #[test]
+    fn reorganises_into_three_compartments() {
+        let moves = reorganise(&split_compartments("aabbca", 1, 3).unwrap()).unwrap();
+        let moves: Vec<_> = moves
+            .iter()
+            .map(|fix| (fix.item, fix.count, fix.from, fix.to))
+            .collect();
+        assert_eq!(moves, vec![('a', 1, 2, 0)]);
+    }
+
+    #[test]
+    fn reorganisation_report_with_other_sizes() {
+        let input = "aabbca\nxyzxyz\nabcdef\nefghij";
+        assert_eq!(
+            reorganisation_report(input, 3, 2).unwrap(),
+            "Rucksack 1: 1x 'a' from compartment 3 to 1 (cost 1) (total cost 1)\n\
+             Rucksack 2: 1x 'x' from compartment 2 to 1 (cost 24), \
+             1x 'y' from compartment 3 to 1 (cost 25), \
+             1x 'z' from compartment 3 to 2 (cost 26) (total cost 75)\n\
+             2 of 4 rucksacks need fixing: 4 item moves, priority cost 76\n\
+             \n\
+             Badges:\n\
+             Group 1 (lines 1-2): no candidate\n\
+             Group 2 (lines 3-4): ambiguous between {e, f}\n\
+             2 of 2 groups without a unique badge"
+        );
+    }
+
+    #[test]
+    fn reorganisation_sizes_must_divide_the_input() {
+        let input = "aabbca\nxyzxyz\nabcdef\nefghij";
+        assert!(matches!(
+            error(reorganisation_report(input, 4, 2)),
+            ProcessError::UnevenCompartments {
+                line: 1,
+                items: 6,
+                compartments: 4
+            }
+        ));
+        assert!(matches!(
+            error(reorganisation_report(input, 3, 3)),
+            ProcessError::IncompleteGroup {
+                line: 4,
+                size: 1,
+                expected: 3
+            }
+        ));
+        assert!(Day03 {}
+            .run_mode("reorganise", input, &with_param("group_size", "0"))
+            .is_err());
     }
 }
@@ -1,65 +1,163 @@
+use std::fmt::Write;
+
+use crate::interval::{self, Interval, IntervalSet};
 use crate::solution::*;
 use simple_error::SimpleError;
 
 pub struct Day04 {}
 
-fn parse_range(input: &str) -> Result<(i64, i64)> {
-    let parts: Vec<&str> = input.split("-").collect();
+type RangePair = (Interval, Interval);
+
+fn parse_line(input: &str) -> Result<RangePair> {
+    let parts: Vec<&str> = input.split(',').collect();
     if parts.len() != 2 {
-        return Err(Box::new(SimpleError::new("Invalid range")));
+        return Err(Box::new(SimpleError::new("Invalid line")));
     }
     Ok((parts[0].parse()?, parts[1].parse()?))
 }
 
-fn parse_line(input: &str) -> Result<((i64, i64), (i64, i64))> {
-    let parts: Vec<&str> = input.split(",").collect();
-    if parts.len() != 2 {
-        return Err(Box::new(SimpleError::new("Invalid line")));
-    }
-    let range1 = parse_range(parts[0])?;
-    let range2 = parse_range(parts[1])?;
-    Ok((range1, range2))
+fn parse_input(input: &str) -> Result<Vec<RangePair>> {
+    input.lines().map(parse_line).collect()
 }
 
-fn ranges_fully_overlap(range1: &(i64, i64), range2: &(i64, i64)) -> bool {
-    if (range1.0 <= range2.0 && range1.1 >= range2.1)
-        || (range2.0 <= range1.0 && range2.1 >= range1.1)
-    {
-        true
-    } else {
-        false
-    }
+/// Every assignment in the input, regardless of pairing.
+fn all_assignments(input: &str) -> Result<Vec<Interval>> {
+    Ok(parse_input(input)?
+        .into_iter()
+        .flat_map(|(first, second)| [first, second])
+        .collect())
 }
 
-fn ranges_overlap(range1: &(i64, i64), range2: &(i64, i64)) -> bool {
-    (range1.0 <= range2.0 && range1.1 >= range2.0)
-        || (range1.0 <= range2.1 && range1.1 >= range2.1)
-        || (range2.0 <= range1.0 && range2.1 >= range1.0)
-        || (range2.0 <= range1.1 && range2.1 >= range1.1)
+fn describe(sections: &IntervalSet) -> String {
+    let mut output = String::new();
+    let _ = writeln!(output, "Sections: {}", sections.len());
+    let _ = write!(output, "Ranges: {}", sections);
+    output
+}
+
+/// Overlaps between any two assignments in the file, not just within a pair.
+fn overlap_report(assignments: &[Interval], top: usize) -> String {
+    let profile = interval::depth_profile(assignments);
+    let max_depth = profile.iter().map(|(_, depth)| *depth).max().unwrap_or(0);
+    let mut output = String::new();
+    let _ = writeln!(output, "Assignments: {}", assignments.len());
+    let _ = writeln!(
+        output,
+        "Overlapping pairs: {}",
+        interval::overlapping_pairs(assignments)
+    );
+    let _ = writeln!(output, "Maximum simultaneous assignments: {}", max_depth);
+
+    let mut contested = profile;
+    contested.sort_by_key(|(interval, depth)| (std::cmp::Reverse(*depth), *interval));
+    contested.truncate(top);
+    let _ = writeln!(output, "Most contested sections:");
+    for (sections, depth) in contested {
+        let _ = writeln!(output, "  {}: {} assignments", sections, depth);
+    }
+    output.trim_end().to_owned()
 }
 
 impl Solution for Day04 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 04 - Part 1");
-        let input = std::fs::read_to_string("data/day04.txt")?;
-        let ranges: Result<Vec<((i64, i64), (i64, i64))>> = input.lines().map(parse_line).collect();
-        let fully_overlapping = ranges?
+    fn part1(&self, input: &str, _ctx: &Context) -> Result<String> {
+        let fully_overlapping = parse_input(input)?
             .into_iter()
-            .filter(|(r1, r2)| ranges_fully_overlap(r1, r2))
+            .filter(|(r1, r2)| r1.contains(r2) || r2.contains(r1))
             .count();
-        println!("Number of pairs that fully overlap: {}", fully_overlapping);
-        Ok(())
+        Ok(fully_overlapping.to_string())
     }
 
-    fn part2(&self) -> Result<()> {
-        println!("Day 04 - Part 2");
-        let input = std::fs::read_to_string("data/day04.txt")?;
-        let ranges: Result<Vec<((i64, i64), (i64, i64))>> = input.lines().map(parse_line).collect();
-        let overlapping = ranges?
+    fn part2(&self, input: &str, _ctx: &Context) -> Result<String> {
+        let overlapping = parse_input(input)?
             .into_iter()
-            .filter(|(r1, r2)| ranges_overlap(r1, r2))
+            .filter(|(r1, r2)| r1.overlaps(r2))
             .count();
-        println!("Number of pairs that overlap at all: {}", overlapping);
-        Ok(())
+        Ok(overlapping.to_string())
+    }
+
+    fn modes(&self) -> &'static [&'static str] {
+        &["coverage", "unclaimed", "overclaimed", "overlaps"]
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["top"]
+    }
+
+    fn run_mode(&self, mode: &str, input: &str, ctx: &Context) -> Result<String> {
+        let assignments = all_assignments(input)?;
+        match mode {
+            // Sections assigned to at least one elf.
+            "coverage" => Ok(describe(&assignments.into_iter().collect())),
+            // Sections between the lowest and highest assigned ones that nobody claims.
+            "unclaimed" => {
+                let covered: IntervalSet = assignments.into_iter().collect();
+                let span: IntervalSet = covered.span().into_iter().collect();
+                Ok(describe(&span.difference(&covered)))
+            }
+            // Sections assigned to three or more elves.
+            "overclaimed" => Ok(describe(&IntervalSet::covered_at_least(&assignments, 3))),
+            "overlaps" => Ok(overlap_report(&assignments, ctx.params.get("top", 10)?)),
+            _ => Err(unsupported_mode(mode, self.modes())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8";
+
+    // Assignments with gaps between them and up to four on one section.
+    const SCATTERED: &str = "1-3,10-12\n2-5,11-11\n20-25,4-4\n10-22,11-14";
+
+    fn run(mode: &str) -> String {
+        let mut ctx = Context::default();
+        ctx.params.insert("top", "3");
+        Day04 {}.run_mode(mode, SCATTERED, &ctx).unwrap()
+    }
+
+    #[test]
+    fn sweep_matches_brute_force() {
+        let assignments = all_assignments(&format!("{}\n{}", EXAMPLE, SCATTERED)).unwrap();
+        let mut pairs = 0;
+        for (idx, first) in assignments.iter().enumerate() {
+            for second in &assignments[idx + 1..] {
+                if first.overlaps(second) {
+                    pairs += 1;
+                }
+            }
+        }
+        assert_eq!(interval::overlapping_pairs(&assignments), pairs);
+
+        let profile = interval::depth_profile(&assignments);
+        for section in 0..=30 {
+            let naive = assignments
+                .iter()
+                .filter(|assignment| assignment.start <= section && section <= assignment.end)
+                .count();
+            let swept = profile
+                .iter()
+                .find(|(piece, _)| piece.start <= section && section <= piece.end)
+                .map_or(0, |(_, depth)| *depth);
+            assert_eq!(swept, naive, "section {}", section);
+        }
+    }
+
+    #[test]
+    fn modes() {
+        assert_eq!(run("coverage"), "Sections: 21\nRanges: 1-5, 10-25");
+        assert_eq!(run("unclaimed"), "Sections: 4\nRanges: 6-9");
+        assert_eq!(run("overclaimed"), "Sections: 2\nRanges: 11-12");
+        assert_eq!(
+            run("overlaps"),
+            "Assignments: 8\n\
+             Overlapping pairs: 9\n\
+             Maximum simultaneous assignments: 4\n\
+             Most contested sections:\n  \
+             11-11: 4 assignments\n  \
+             12-12: 3 assignments\n  \
+             2-4: 2 assignments"
+        );
     }
 }
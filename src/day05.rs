@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::error::Error;
+use std::fmt::Display;
 
 use crate::solution::*;
 use simple_error::SimpleError;
@@ -24,108 +27,1530 @@ fn parse_stacks(input: &str) -> HashMap<i32, Vec<char>> {
     for i in (0..(lines.len() - 1)).rev() {
         for (label, idx) in stack_positions.iter() {
             if *idx < lines[i].len() {
-                let crate_char = lines[i].chars().skip(*idx).next().unwrap();
+                let crate_char = lines[i].chars().nth(*idx).unwrap();
                 if crate_char != ' ' {
                     stacks.get_mut(label).unwrap().push(crate_char);
                 }
             }
         }
     }
-    return stacks;
+    stacks
 }
 
-fn process_line(stacks: &mut HashMap<i32, Vec<char>>, line: &str) -> Result<()> {
-    let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() != 6 {
-        return Err(Box::new(SimpleError::new(format!(
-            "Invalid line: {}",
-            line
-        ))));
+/// Renders `stacks` in the puzzle's drawing format, the inverse of
+/// `parse_stacks`. Each stack gets a column wide enough for its label, and
+/// crates sit above the first character of the label.
+fn render_stacks(stacks: &HashMap<i32, Vec<char>>) -> String {
+    let mut labels: Vec<i32> = stacks.keys().copied().collect();
+    labels.sort_unstable();
+    let widths: Vec<usize> = labels
+        .iter()
+        .map(|label| (label.to_string().len() + 1).max(3))
+        .collect();
+    let height = stacks.values().map(Vec::len).max().unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for level in (0..height).rev() {
+        let cells: Vec<String> = labels
+            .iter()
+            .zip(&widths)
+            .map(|(label, width)| match stacks[label].get(level) {
+                Some(c) => format!("{:<width$}", format!("[{}]", c)),
+                None => " ".repeat(*width),
+            })
+            .collect();
+        lines.push(cells.join(" "));
     }
-    let (num_crates, source, dest) = (
-        parts[1].parse::<i32>().unwrap(),
-        parts[3].parse::<i32>().unwrap(),
-        parts[5].parse::<i32>().unwrap(),
-    );
-    for _ in 0..num_crates {
-        if let Some(c) = stacks.get_mut(&source).and_then(|x| x.pop()) {
-            stacks.get_mut(&dest).unwrap().push(c);
-        } else {
-            return Err(Box::new(SimpleError::new("invalid instructions")));
+    let cells: Vec<String> = labels
+        .iter()
+        .zip(&widths)
+        .map(|(label, width)| format!(" {:<w$}", label, w = width - 1))
+        .collect();
+    lines.push(cells.join(" "));
+    lines.join("\n")
+}
+
+/// A parsed `move <count> from <source> to <dest>` line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Instruction {
+    count: usize,
+    source: i32,
+    dest: i32,
+}
+
+impl Instruction {
+    fn parse(line: &str) -> Result<Self> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() != 6 {
+            return Err(Box::new(SimpleError::new(format!(
+                "Invalid line: {}",
+                line
+            ))));
         }
+        Ok(Self {
+            count: parts[1].parse()?,
+            source: parts[3].parse()?,
+            dest: parts[5].parse()?,
+        })
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count, self.source, self.dest
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct CraneStats {
+    lifts: usize,
+    /// Total number of stack positions the crane's hook travelled while loaded.
+    distance: i64,
+}
+
+/// A crane model: how an instruction is carried out as a series of lifts.
+trait Crane {
+    fn name(&self) -> String;
+    /// Whether the crane has to pass every stack between source and
+    /// destination, so that a gap in the labels can't be crossed.
+    fn needs_contiguous_labels(&self) -> bool {
+        false
     }
+    fn apply(
+        &self,
+        stacks: &mut HashMap<i32, Vec<char>>,
+        instruction: &Instruction,
+        stats: &mut CraneStats,
+    ) -> Result<()>;
+}
+
+/// Moves the top `count` crates of `source` onto `dest` in one lift, keeping
+/// their order.
+fn lift(
+    stacks: &mut HashMap<i32, Vec<char>>,
+    source: i32,
+    dest: i32,
+    count: usize,
+    stats: &mut CraneStats,
+) -> Result<()> {
+    if !stacks.contains_key(&dest) {
+        return Err(Box::new(SimpleError::new("invalid instructions")));
+    }
+    let from = match stacks.get_mut(&source) {
+        Some(from) if from.len() >= count => from,
+        _ => return Err(Box::new(SimpleError::new("invalid instructions"))),
+    };
+    let crates = from.split_off(from.len() - count);
+    stacks.get_mut(&dest).unwrap().extend(crates);
+    stats.lifts += 1;
+    stats.distance += (dest - source).abs() as i64;
     Ok(())
 }
 
-fn process_line_part2(stacks: &mut HashMap<i32, Vec<char>>, line: &str) -> Result<()> {
-    let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() != 6 {
+/// Carries out `instruction` as lifts of at most `capacity` crates each.
+fn lift_in_batches(
+    stacks: &mut HashMap<i32, Vec<char>>,
+    instruction: &Instruction,
+    capacity: usize,
+    stats: &mut CraneStats,
+) -> Result<()> {
+    let mut remaining = instruction.count;
+    while remaining > 0 {
+        let batch = remaining.min(capacity);
+        lift(stacks, instruction.source, instruction.dest, batch, stats)?;
+        remaining -= batch;
+    }
+    Ok(())
+}
+
+/// Moves one crate at a time.
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_owned()
+    }
+
+    fn apply(
+        &self,
+        stacks: &mut HashMap<i32, Vec<char>>,
+        instruction: &Instruction,
+        stats: &mut CraneStats,
+    ) -> Result<()> {
+        lift_in_batches(stacks, instruction, 1, stats)
+    }
+}
+
+/// Moves all the crates of an instruction at once.
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_owned()
+    }
+
+    fn apply(
+        &self,
+        stacks: &mut HashMap<i32, Vec<char>>,
+        instruction: &Instruction,
+        stats: &mut CraneStats,
+    ) -> Result<()> {
+        lift_in_batches(stacks, instruction, usize::MAX, stats)
+    }
+}
+
+/// Moves at most `capacity` crates per lift, top ones first.
+struct CapacityLimited {
+    capacity: usize,
+}
+
+impl Crane for CapacityLimited {
+    fn name(&self) -> String {
+        format!("capacity-limited crane (capacity {})", self.capacity)
+    }
+
+    fn apply(
+        &self,
+        stacks: &mut HashMap<i32, Vec<char>>,
+        instruction: &Instruction,
+        stats: &mut CraneStats,
+    ) -> Result<()> {
+        lift_in_batches(stacks, instruction, self.capacity, stats)
+    }
+}
+
+/// Can only reach the neighbouring stacks, so each batch of up to `capacity`
+/// crates is set down on every stack between source and destination on the way.
+struct AdjacentOnly {
+    capacity: usize,
+}
+
+impl Crane for AdjacentOnly {
+    fn name(&self) -> String {
+        if self.capacity == usize::MAX {
+            "adjacent-only crane".to_owned()
+        } else {
+            format!("adjacent-only crane (capacity {})", self.capacity)
+        }
+    }
+
+    fn needs_contiguous_labels(&self) -> bool {
+        true
+    }
+
+    fn apply(
+        &self,
+        stacks: &mut HashMap<i32, Vec<char>>,
+        instruction: &Instruction,
+        stats: &mut CraneStats,
+    ) -> Result<()> {
+        let step = (instruction.dest - instruction.source).signum();
+        let mut remaining = instruction.count;
+        while remaining > 0 {
+            let batch = remaining.min(self.capacity);
+            let mut position = instruction.source;
+            while position != instruction.dest {
+                lift(stacks, position, position + step, batch, stats)?;
+                position += step;
+            }
+            remaining -= batch;
+        }
+        Ok(())
+    }
+}
+
+/// Names accepted by the `crane` parameter.
+const CRANES: &[&str] = &["9000", "9001", "capacity", "adjacent"];
+
+/// The crane picked by the `crane` and `capacity` parameters.
+fn crane_from_params(ctx: &Context) -> Result<Box<dyn Crane>> {
+    let capacity: Option<usize> = ctx.params.get_opt("capacity")?;
+    if capacity == Some(0) {
+        return Err(Box::new(SimpleError::new(
+            "Parameter 'capacity' must be at least 1",
+        )));
+    }
+    match ctx.params.get("crane", "9001".to_owned())?.as_str() {
+        "9000" => Ok(Box::new(CrateMover9000)),
+        "9001" => Ok(Box::new(CrateMover9001)),
+        "capacity" => Ok(Box::new(CapacityLimited {
+            capacity: capacity.unwrap_or(3),
+        })),
+        "adjacent" => Ok(Box::new(AdjacentOnly {
+            capacity: capacity.unwrap_or(usize::MAX),
+        })),
+        name => Err(Box::new(SimpleError::new(format!(
+            "Unknown crane '{}', expected one of: {}",
+            name,
+            CRANES.join(", ")
+        )))),
+    }
+}
+
+fn process_line(
+    stacks: &mut HashMap<i32, Vec<char>>,
+    line: &str,
+    crane: &dyn Crane,
+    stats: &mut CraneStats,
+) -> Result<()> {
+    crane.apply(stacks, &Instruction::parse(line)?, stats)
+}
+
+/// Why an instruction can't be carried out.
+#[derive(Debug)]
+enum InvalidReason {
+    Malformed,
+    UnknownLabel(i32),
+    SameStack,
+    EmptySource {
+        available: usize,
+    },
+    /// The drawing's labels skip from one number to a later one, which the
+    /// adjacent-only crane can't cross.
+    LabelGap {
+        after: i32,
+        next: i32,
+    },
+}
+
+/// The first instruction that can't be carried out, and the stack heights
+/// just before it.
+#[derive(Debug)]
+struct InstructionError {
+    /// 1-based line number in the whole input.
+    line: usize,
+    text: String,
+    heights: Vec<(i32, usize)>,
+    reason: InvalidReason,
+}
+
+impl Display for InstructionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {} ('{}'): ", self.line, self.text)?;
+        match &self.reason {
+            InvalidReason::Malformed => {
+                write!(f, "expected 'move <count> from <stack> to <stack>'")?
+            }
+            InvalidReason::UnknownLabel(label) => write!(f, "unknown stack {}", label)?,
+            InvalidReason::SameStack => write!(f, "source and destination are the same")?,
+            InvalidReason::EmptySource { available } => {
+                write!(f, "source holds only {} crates", available)?
+            }
+            InvalidReason::LabelGap { after, next } => {
+                write!(f, "stack labels jump from {} to {}", after, next)?
+            }
+        }
+        write!(f, "; stack heights: {}", format_heights(&self.heights))
+    }
+}
+
+impl Error for InstructionError {}
+
+fn format_heights(heights: &[(i32, usize)]) -> String {
+    let parts: Vec<String> = heights
+        .iter()
+        .map(|(label, height)| format!("{}:{}", label, height))
+        .collect();
+    parts.join(" ")
+}
+
+/// Splits the input into the drawing, the instructions and the line number
+/// the instructions start on.
+fn split_input(input: &str) -> Result<(&str, &str, usize)> {
+    let parts: Vec<&str> = input.split("\n\n").collect();
+    if parts.len() != 2 {
+        return Err(Box::new(SimpleError::new("Invalid input")));
+    }
+    Ok((parts[0], parts[1], parts[0].lines().count() + 2))
+}
+
+/// Dry run of `instructions` against the heights of `stacks`, leaving the
+/// stacks untouched. Every crane model moves the same number of crates
+/// between the same stacks, so validity doesn't depend on the model, except
+/// that `crane` may need the labels to be contiguous. Returns the final
+/// heights.
+fn validate(
+    stacks: &HashMap<i32, Vec<char>>,
+    instructions: &str,
+    first_line: usize,
+    crane: &dyn Crane,
+) -> std::result::Result<Vec<(i32, usize)>, InstructionError> {
+    let mut heights: Vec<(i32, usize)> = stacks
+        .iter()
+        .map(|(label, crates)| (*label, crates.len()))
+        .collect();
+    heights.sort_unstable();
+    let gap = heights
+        .windows(2)
+        .find(|pair| pair[1].0 != pair[0].0 + 1)
+        .filter(|_| crane.needs_contiguous_labels());
+    if let Some(pair) = gap {
+        let labels: Vec<String> = heights.iter().map(|(label, _)| label.to_string()).collect();
+        return Err(InstructionError {
+            // The label row, just above the blank line.
+            line: first_line - 2,
+            text: labels.join(" "),
+            reason: InvalidReason::LabelGap {
+                after: pair[0].0,
+                next: pair[1].0,
+            },
+            heights,
+        });
+    }
+    let position = |heights: &[(i32, usize)], label: i32| {
+        heights
+            .binary_search_by_key(&label, |(label, _)| *label)
+            .map_err(|_| InvalidReason::UnknownLabel(label))
+    };
+    for (idx, text) in instructions.lines().enumerate() {
+        let checked = Instruction::parse(text)
+            .map_err(|_| InvalidReason::Malformed)
+            .and_then(|instruction| {
+                let source = position(&heights, instruction.source)?;
+                let dest = position(&heights, instruction.dest)?;
+                if source == dest {
+                    return Err(InvalidReason::SameStack);
+                }
+                if heights[source].1 < instruction.count {
+                    return Err(InvalidReason::EmptySource {
+                        available: heights[source].1,
+                    });
+                }
+                Ok((source, dest, instruction.count))
+            });
+        match checked {
+            Ok((source, dest, count)) => {
+                heights[source].1 -= count;
+                heights[dest].1 += count;
+            }
+            Err(reason) => {
+                return Err(InstructionError {
+                    line: first_line + idx,
+                    text: text.to_owned(),
+                    heights,
+                    reason,
+                })
+            }
+        }
+    }
+    Ok(heights)
+}
+
+/// Runs the whole procedure with `crane`, returning the final stacks. Nothing
+/// is moved unless every instruction is valid.
+fn rearrange(input: &str, crane: &dyn Crane) -> Result<(HashMap<i32, Vec<char>>, CraneStats)> {
+    let (drawing, instructions, first_line) = split_input(input)?;
+    let mut stacks = parse_stacks(drawing);
+    validate(&stacks, instructions, first_line, crane)?;
+    let mut stats = CraneStats::default();
+    for line in instructions.lines() {
+        process_line(&mut stacks, line, crane, &mut stats)?;
+    }
+    Ok((stacks, stats))
+}
+
+/// The top crate of each stack in label order. Empty stacks are skipped.
+fn top_crates(stacks: &HashMap<i32, Vec<char>>) -> String {
+    let mut labels: Vec<&i32> = stacks.keys().collect();
+    labels.sort_unstable();
+    labels
+        .into_iter()
+        .filter_map(|label| stacks[label].last())
+        .collect()
+}
+
+/// What a single stack should look like once the plan has run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StackGoal {
+    /// Exactly these crates, bottom first.
+    Crates(Vec<char>),
+    /// This crate on top.
+    Top(char),
+    Empty,
+    Any,
+}
+
+impl StackGoal {
+    fn is_met(&self, stack: &[char]) -> bool {
+        match self {
+            StackGoal::Crates(crates) => stack == crates.as_slice(),
+            StackGoal::Top(c) => stack.last() == Some(c),
+            StackGoal::Empty => stack.is_empty(),
+            StackGoal::Any => true,
+        }
+    }
+}
+
+/// Goals for every stack from a top-of-stacks string in label order, where
+/// `?` leaves a stack unconstrained and `-` asks for it to be empty.
+fn goals_from_tops(tops: &str, labels: &[i32]) -> Result<Vec<StackGoal>> {
+    if tops.chars().count() != labels.len() {
         return Err(Box::new(SimpleError::new(format!(
-            "Invalid line: {}",
-            line
+            "Target '{}' must have one character for each of the {} stacks",
+            tops,
+            labels.len()
         ))));
     }
-    let (num_crates, source, dest) = (
-        parts[1].parse::<i32>().unwrap(),
-        parts[3].parse::<i32>().unwrap(),
-        parts[5].parse::<i32>().unwrap(),
-    );
-    let mut temp_stack: Vec<char> = Vec::new();
-    for _ in 0..num_crates {
-        if let Some(c) = stacks.get_mut(&source).and_then(|x| x.pop()) {
-            temp_stack.push(c);
-        } else {
-            return Err(Box::new(SimpleError::new("invalid instructions")));
+    Ok(tops
+        .chars()
+        .map(|c| match c {
+            '?' => StackGoal::Any,
+            '-' => StackGoal::Empty,
+            c => StackGoal::Top(c),
+        })
+        .collect())
+}
+
+/// Goals for every stack from a full target drawing.
+fn goals_from_drawing(drawing: &str, labels: &[i32]) -> Result<Vec<StackGoal>> {
+    let target = parse_stacks(drawing);
+    let mut target_labels: Vec<i32> = target.keys().copied().collect();
+    target_labels.sort_unstable();
+    if target_labels != labels {
+        return Err(Box::new(SimpleError::new(
+            "Target drawing must have the same stacks as the input",
+        )));
+    }
+    Ok(labels
+        .iter()
+        .map(|label| StackGoal::Crates(target[label].clone()))
+        .collect())
+}
+
+/// A node of the search: the stacks in label order, each followed by a
+/// newline (which can't be a crate), so states are cheap to hash and store.
+type PlanState = String;
+
+/// The stacks in label order, as the heuristics see them.
+type StackView<'a> = [&'a [char]];
+
+/// The stacks in label order.
+type PlanStacks = Vec<Vec<char>>;
+
+fn encode_state(stacks: &StackView) -> PlanState {
+    let mut state = String::with_capacity(stacks.iter().map(|stack| stack.len() + 1).sum());
+    for stack in stacks {
+        state.extend(stack.iter());
+        state.push('\n');
+    }
+    state
+}
+
+struct PlanNode {
+    state: PlanState,
+    moves: usize,
+    /// The node and instruction this state was reached from.
+    parent: Option<(usize, Instruction)>,
+}
+
+#[derive(Debug)]
+struct Plan {
+    instructions: Vec<Instruction>,
+    /// False if the search had to give up on optimality to find a plan.
+    minimal: bool,
+}
+
+/// Searches for the shortest list of instructions that takes the stacks to
+/// the goals when carried out by `crane`.
+struct Planner<'a> {
+    crane: &'a dyn Crane,
+    labels: Vec<i32>,
+    goals: Vec<StackGoal>,
+    max_states: usize,
+    cancel: &'a CancellationToken,
+}
+
+impl Planner<'_> {
+    fn to_stacks(&self, state: &PlanState) -> HashMap<i32, Vec<char>> {
+        self.labels
+            .iter()
+            .zip(state.split_terminator('\n'))
+            .map(|(label, crates)| (*label, crates.chars().collect()))
+            .collect()
+    }
+
+    fn view<'s>(&self, stacks: &'s HashMap<i32, Vec<char>>) -> Vec<&'s [char]> {
+        self.labels
+            .iter()
+            .map(|label| stacks[label].as_slice())
+            .collect()
+    }
+
+    /// An instruction changes at most two stacks, its source and destination,
+    /// so this never overestimates the remaining number of instructions.
+    fn estimate(&self, stacks: &StackView) -> usize {
+        let unmet = stacks
+            .iter()
+            .zip(&self.goals)
+            .filter(|(stack, goal)| !goal.is_met(stack))
+            .count();
+        unmet.div_ceil(2)
+    }
+
+    /// Calls `visit` with every instruction that is valid in `stacks` and the
+    /// stacks it leads to. Each instruction is carried out in place and then
+    /// undone, which works for every crane because they all leave the moved
+    /// crates on top of the destination and the stacks in between as they
+    /// were.
+    fn for_each_successor(
+        &self,
+        stacks: &mut HashMap<i32, Vec<char>>,
+        mut visit: impl FnMut(Instruction, &StackView),
+    ) -> Result<()> {
+        let mut stats = CraneStats::default();
+        let mut taken = Vec::new();
+        for source in &self.labels {
+            for dest in self.labels.iter().filter(|dest| *dest != source) {
+                for count in 1..=stacks[source].len() {
+                    let instruction = Instruction {
+                        count,
+                        source: *source,
+                        dest: *dest,
+                    };
+                    let from = &stacks[source];
+                    taken.clear();
+                    taken.extend_from_slice(&from[from.len() - count..]);
+                    self.crane.apply(stacks, &instruction, &mut stats)?;
+                    visit(instruction, &self.view(stacks));
+                    let to = stacks.get_mut(dest).unwrap();
+                    to.truncate(to.len() - count);
+                    stacks.get_mut(source).unwrap().extend_from_slice(&taken);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A* search ordered by moves plus `estimate`, so the first plan found is
+    /// minimal. Returns `None` if `max_states` states were expanded without
+    /// reaching the goal.
+    fn search(&self, start: &HashMap<i32, Vec<char>>) -> Result<Option<Vec<Instruction>>> {
+        let priority = |stacks: &StackView, moves: usize| moves + self.estimate(stacks);
+        let start_view = self.view(start);
+        let start_state = encode_state(&start_view);
+        let mut nodes = vec![PlanNode {
+            state: start_state.clone(),
+            moves: 0,
+            parent: None,
+        }];
+        // The node holding the fewest moves found so far to each state.
+        let mut best: HashMap<PlanState, usize> = HashMap::from([(start_state, 0)]);
+        let mut queue = BinaryHeap::from([Reverse((priority(&start_view, 0), 0, 0))]);
+        let mut expanded = 0;
+        while let Some(Reverse((_, moves, id))) = queue.pop() {
+            if best[&nodes[id].state] != id {
+                continue;
+            }
+            let mut stacks = self.to_stacks(&nodes[id].state);
+            if self.estimate(&self.view(&stacks)) == 0 {
+                let mut instructions = Vec::new();
+                let mut current = id;
+                while let Some((parent, instruction)) = nodes[current].parent {
+                    instructions.push(instruction);
+                    current = parent;
+                }
+                instructions.reverse();
+                return Ok(Some(instructions));
+            }
+            expanded += 1;
+            if expanded > self.max_states {
+                return Ok(None);
+            }
+            if expanded % 64 == 0 {
+                self.cancel.check()?;
+            }
+            let next_moves = moves + 1;
+            self.for_each_successor(&mut stacks, |instruction, next| {
+                let state = encode_state(next);
+                if best
+                    .get(&state)
+                    .is_some_and(|known| nodes[*known].moves <= next_moves)
+                {
+                    return;
+                }
+                let next_id = nodes.len();
+                queue.push(Reverse((priority(next, next_moves), next_moves, next_id)));
+                best.insert(state.clone(), next_id);
+                nodes.push(PlanNode {
+                    state,
+                    moves: next_moves,
+                    parent: Some((id, instruction)),
+                });
+            })?;
         }
+        Err(Box::new(SimpleError::new("The target can't be reached")))
     }
-    for _ in 0..num_crates {
-        if let Some(c) = temp_stack.pop() {
-            stacks.get_mut(&dest).unwrap().push(c);
+
+    /// Whether the crates on hand can satisfy the goals at all.
+    fn has_crates_for(&self, stacks: &HashMap<i32, Vec<char>>) -> bool {
+        let mut available: HashMap<char, usize> = HashMap::new();
+        for c in stacks.values().flatten() {
+            *available.entry(*c).or_default() += 1;
+        }
+        let mut needed: HashMap<char, usize> = HashMap::new();
+        let mut exact = false;
+        for goal in &self.goals {
+            match goal {
+                StackGoal::Crates(crates) => {
+                    exact = true;
+                    for c in crates {
+                        *needed.entry(*c).or_default() += 1;
+                    }
+                }
+                StackGoal::Top(c) => *needed.entry(*c).or_default() += 1,
+                StackGoal::Empty | StackGoal::Any => {}
+            }
+        }
+        if exact {
+            needed == available
         } else {
-            return Err(Box::new(SimpleError::new("invalid instructions")));
+            needed
+                .iter()
+                .all(|(c, count)| available.get(c).is_some_and(|have| have >= count))
         }
     }
-    Ok(())
+
+    /// A minimal plan if one is found within the state budget, otherwise a
+    /// constructed one.
+    fn plan(&self, stacks: &HashMap<i32, Vec<char>>) -> Result<Plan> {
+        if !self.has_crates_for(stacks) {
+            return Err(Box::new(SimpleError::new(
+                "The target needs crates that aren't in the drawing",
+            )));
+        }
+        if let Some(instructions) = self.search(stacks)? {
+            return Ok(Plan {
+                instructions,
+                minimal: true,
+            });
+        }
+        match self.construct(stacks)? {
+            Some(instructions) => Ok(Plan {
+                instructions,
+                minimal: false,
+            }),
+            None => Err(Box::new(SimpleError::new(format!(
+                "No plan found within {} states, try raising max_states",
+                self.max_states
+            )))),
+        }
+    }
+
+    /// Single-crate instructions that reach the goals without searching: the
+    /// first stack whose goal is unmet is worked on until it is met, digging
+    /// crates out of the way onto the stacks where they do least harm. Every
+    /// crane moves a single crate the same way, so the plan suits all of them.
+    /// Returns `None` if the stacks keep undoing each other.
+    fn construct(&self, stacks: &HashMap<i32, Vec<char>>) -> Result<Option<Vec<Instruction>>> {
+        let mut state: PlanStacks = self
+            .labels
+            .iter()
+            .map(|label| stacks[label].clone())
+            .collect();
+        let crates: usize = state.iter().map(Vec::len).sum();
+        let limit = 4 * (crates + 1) * state.len();
+        let mut instructions = Vec::new();
+        while let Some(target) = (0..state.len()).find(|idx| !self.goals[*idx].is_met(&state[*idx]))
+        {
+            while !self.goals[target].is_met(&state[target]) {
+                if instructions.len() >= limit {
+                    return Ok(None);
+                }
+                self.cancel.check()?;
+                let Some(moves) = self.next_moves(&state, target) else {
+                    return Ok(None);
+                };
+                for (from, to) in moves {
+                    let c = state[from].pop().unwrap();
+                    state[to].push(c);
+                    instructions.push(Instruction {
+                        count: 1,
+                        source: self.labels[from],
+                        dest: self.labels[to],
+                    });
+                }
+            }
+        }
+        Ok(Some(instructions))
+    }
+
+    /// The next single-crate moves towards meeting the goal of stack `target`.
+    fn next_moves(&self, state: &PlanStacks, target: usize) -> Option<Vec<(usize, usize)>> {
+        let stack = &state[target];
+        let wanted = match &self.goals[target] {
+            StackGoal::Crates(crates) => {
+                let kept = common_prefix(stack, crates);
+                (kept == stack.len()).then(|| crates[kept])
+            }
+            // Dig down to a copy already in the stack if that is no more work
+            // than fetching one.
+            StackGoal::Top(c) => {
+                let own = stack.iter().rev().position(|crate_| crate_ == c);
+                let fetch = self.source_for(state, target, *c).map(|(_, cost)| cost + 1);
+                match (own, fetch) {
+                    (Some(own), Some(fetch)) if own <= fetch => None,
+                    (Some(_), None) => None,
+                    _ => Some(*c),
+                }
+            }
+            StackGoal::Empty | StackGoal::Any => None,
+        };
+        let from = match wanted {
+            None => target,
+            Some(c) => {
+                let (source, _) = self.source_for(state, target, c)?;
+                if state[source].last() == Some(&c) {
+                    return Some(vec![(source, target)]);
+                }
+                source
+            }
+        };
+        let (dump, met) = self.dump_for(state, from, &[target, from])?;
+        // Slip the crate under the top of a stack whose top is already right,
+        // parking that top on any other stack meanwhile.
+        if met && matches!(self.goals[dump], StackGoal::Top(_)) {
+            let park = (0..state.len())
+                .filter(|idx| *idx != from && *idx != dump)
+                .min_by_key(|idx| idx.abs_diff(dump))?;
+            return Some(vec![(dump, park), (from, dump), (park, dump)]);
+        }
+        Some(vec![(from, dump)])
+    }
+
+    /// The stack to take crate `c` from for stack `target`, and the cost of
+    /// getting to it: the crates above it, plus a large penalty if taking it
+    /// undoes another stack's goal.
+    fn source_for(&self, state: &PlanStacks, target: usize, c: char) -> Option<(usize, usize)> {
+        (0..state.len())
+            .filter(|idx| *idx != target)
+            .filter_map(|idx| {
+                let stack = &state[idx];
+                let depth = stack.iter().rev().position(|crate_| *crate_ == c)?;
+                let position = stack.len() - 1 - depth;
+                // Taking the crate a stack wants on top only moves the problem.
+                let penalty = match &self.goals[idx] {
+                    StackGoal::Top(x) if *x == c && depth == 0 => 10_000,
+                    StackGoal::Top(_) if self.goals[idx].is_met(stack) => 1000,
+                    StackGoal::Crates(crates) if position < common_prefix(stack, crates) => 1000,
+                    _ => 0,
+                };
+                Some((idx, depth + penalty))
+            })
+            .min_by_key(|(idx, cost)| (*cost, *idx))
+    }
+
+    /// Where to put the top crate of stack `from` while digging, avoiding
+    /// `exclude`, and whether that stack's goal is currently met. Stacks that
+    /// want the crate come first, then ones without a goal or already waiting
+    /// for crates, then stacks still to be emptied, stacks whose goal is met,
+    /// and finally stacks that are already empty as they should be. Nearer
+    /// stacks win ties.
+    fn dump_for(
+        &self,
+        state: &PlanStacks,
+        from: usize,
+        exclude: &[usize],
+    ) -> Option<(usize, bool)> {
+        let c = *state[from].last()?;
+        let dump = (0..state.len())
+            .filter(|idx| !exclude.contains(idx))
+            .min_by_key(|idx| {
+                let stack = &state[*idx];
+                let rank = match &self.goals[*idx] {
+                    StackGoal::Top(x) if *x == c && stack.last() != Some(x) => 0,
+                    StackGoal::Crates(crates) => {
+                        let kept = common_prefix(stack, crates);
+                        if kept < stack.len() {
+                            1
+                        } else if crates.get(kept) == Some(&c) {
+                            0
+                        } else if kept == crates.len() {
+                            3
+                        } else {
+                            2
+                        }
+                    }
+                    StackGoal::Any => 1,
+                    StackGoal::Top(x) if stack.last() == Some(x) => 3,
+                    StackGoal::Top(_) => 1,
+                    StackGoal::Empty if stack.is_empty() => 4,
+                    StackGoal::Empty => 2,
+                };
+                (rank, idx.abs_diff(from))
+            })?;
+        Some((dump, self.goals[dump].is_met(&state[dump])))
+    }
 }
 
-impl Solution for Day05 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 05 - Part 1");
-        let input = std::fs::read_to_string("data/day05.txt")?;
-        let parts: Vec<&str> = input.split("\n\n").collect();
-        if parts.len() != 2 {
-            return Err(Box::new(SimpleError::new("Invalid input")));
-        }
-        let mut stacks = parse_stacks(parts[0]);
-        for line in parts[1].lines() {
-            process_line(&mut stacks, line)?;
-        }
-        println!("Top of the stacks:");
-        for i in 0..stacks.len() {
-            let top = stacks[&((i + 1) as i32)].last().unwrap();
-            print!("{}", top);
-        }
-        println!();
+/// How many crates at the bottom of `stack` already match `crates`.
+fn common_prefix(stack: &[char], crates: &[char]) -> usize {
+    stack.iter().zip(crates).take_while(|(a, b)| a == b).count()
+}
+
+fn plan_report(input: &str, ctx: &Context) -> Result<String> {
+    let (drawing, _, _) = split_input(input)?;
+    let stacks = parse_stacks(drawing);
+    let mut labels: Vec<i32> = stacks.keys().copied().collect();
+    labels.sort_unstable();
+    let (goals, target) = match (
+        ctx.params.get_opt::<String>("tops")?,
+        ctx.params.get_opt::<String>("target_file")?,
+    ) {
+        (Some(tops), None) => (goals_from_tops(&tops, &labels)?, format!("tops {}", tops)),
+        (None, Some(path)) => {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|error| SimpleError::new(format!("{}: {}", path, error)))?;
+            let drawing = contents.split("\n\n").next().unwrap_or("").trim_end();
+            (
+                goals_from_drawing(drawing, &labels)?,
+                format!("drawing in {}", path),
+            )
+        }
+        _ => {
+            return Err(Box::new(SimpleError::new(
+                "Set exactly one of the 'tops' or 'target_file' parameters",
+            )))
+        }
+    };
+    let crane = crane_from_params(ctx)?;
+    let planner = Planner {
+        crane: crane.as_ref(),
+        labels,
+        goals,
+        max_states: ctx.params.get("max_states", 500)?,
+        cancel: &ctx.cancel,
+    };
+    let plan = planner.plan(&stacks)?;
+
+    let mut lines = vec![
+        format!("Crane: {}", crane.name()),
+        format!("Target: {}", target),
+        format!(
+            "Plan: {} instructions{}",
+            plan.instructions.len(),
+            if plan.minimal {
+                " (minimal)"
+            } else {
+                " (not proven minimal)"
+            }
+        ),
+    ];
+    lines.extend(plan.instructions.iter().map(Instruction::to_string));
+    Ok(lines.join("\n"))
+}
+
+/// Stack states along a list of instructions. Only the current state is
+/// kept; moving backwards undoes instructions using the crates each one took
+/// off its source, so jumping anywhere costs time proportional to the
+/// distance rather than a copy of the stacks per instruction.
+struct History<'a> {
+    crane: &'a dyn Crane,
+    stacks: HashMap<i32, Vec<char>>,
+    instructions: Vec<Instruction>,
+    /// The crates each applied instruction took off its source, bottom first.
+    /// Its length is the number of instructions applied so far.
+    taken: Vec<Vec<char>>,
+}
+
+impl<'a> History<'a> {
+    /// Starts before the first instruction. The instructions must be valid.
+    fn new(
+        crane: &'a dyn Crane,
+        stacks: HashMap<i32, Vec<char>>,
+        instructions: Vec<Instruction>,
+    ) -> Self {
+        Self {
+            crane,
+            stacks,
+            instructions,
+            taken: Vec::new(),
+        }
+    }
+
+    fn position(&self) -> usize {
+        self.taken.len()
+    }
+
+    /// Applies the next instruction. Nothing is saved to roll back with: the
+    /// instructions were validated up front, so the crane can't fail part way.
+    fn step_forward(&mut self) -> Result<()> {
+        let instruction = self.instructions[self.position()];
+        let taken = match self.stacks.get(&instruction.source) {
+            Some(source) if source.len() >= instruction.count => {
+                source[source.len() - instruction.count..].to_vec()
+            }
+            _ => return Err(Box::new(SimpleError::new("invalid instructions"))),
+        };
+        let mut stats = CraneStats::default();
+        self.crane
+            .apply(&mut self.stacks, &instruction, &mut stats)?;
+        self.taken.push(taken);
         Ok(())
     }
 
-    fn part2(&self) -> Result<()> {
-        println!("Day 05 - Part 2");
-        let input = std::fs::read_to_string("data/day05.txt")?;
-        let parts: Vec<&str> = input.split("\n\n").collect();
-        if parts.len() != 2 {
-            return Err(Box::new(SimpleError::new("Invalid input")));
+    /// Every crane leaves the moved crates on top of the destination and the
+    /// stacks in between as they were, so undoing is the same for all of them.
+    fn step_back(&mut self) {
+        let taken = self.taken.pop().unwrap();
+        let instruction = self.instructions[self.position()];
+        let dest = self.stacks.get_mut(&instruction.dest).unwrap();
+        dest.truncate(dest.len() - instruction.count);
+        self.stacks
+            .get_mut(&instruction.source)
+            .unwrap()
+            .extend(taken);
+    }
+
+    /// Moves to the state after the first `position` instructions.
+    fn jump(&mut self, position: usize) -> Result<()> {
+        if position > self.instructions.len() {
+            return Err(Box::new(SimpleError::new(format!(
+                "Instruction {} is out of range, there are {}",
+                position,
+                self.instructions.len()
+            ))));
         }
-        let mut stacks = parse_stacks(parts[0]);
-        for line in parts[1].lines() {
-            process_line_part2(&mut stacks, line)?;
+        while self.position() < position {
+            self.step_forward()?;
         }
-        println!("Top of the stacks:");
-        for i in 0..stacks.len() {
-            let top = stacks[&((i + 1) as i32)].last().unwrap();
-            print!("{}", top);
+        while self.position() > position {
+            self.step_back();
         }
-        println!();
         Ok(())
     }
+
+    fn undo(&mut self, count: usize) -> Result<()> {
+        match self.position().checked_sub(count) {
+            Some(position) => self.jump(position),
+            None => Err(Box::new(SimpleError::new(format!(
+                "Can't undo {} instructions, only {} applied",
+                count,
+                self.position()
+            )))),
+        }
+    }
+
+    fn redo(&mut self, count: usize) -> Result<()> {
+        self.jump(self.position() + count)
+    }
+
+    /// A copy of the state after the first `position` instructions.
+    fn snapshot(&mut self, position: usize) -> Result<HashMap<i32, Vec<char>>> {
+        self.jump(position)?;
+        Ok(self.stacks.clone())
+    }
+}
+
+/// What changed on each stack between two states, as the crates taken off
+/// and put on above the part they have in common.
+fn diff_stacks(before: &HashMap<i32, Vec<char>>, after: &HashMap<i32, Vec<char>>) -> String {
+    let mut labels: Vec<&i32> = before.keys().collect();
+    labels.sort_unstable();
+    let mut lines = Vec::new();
+    for label in labels {
+        let (old, new) = (&before[label], &after[label]);
+        let kept = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        if kept == old.len() && kept == new.len() {
+            continue;
+        }
+        let mut change = format!("  stack {}:", label);
+        if kept < old.len() {
+            change.push_str(&format!(" -{}", old[kept..].iter().collect::<String>()));
+        }
+        if kept < new.len() {
+            change.push_str(&format!(" +{}", new[kept..].iter().collect::<String>()));
+        }
+        lines.push(change);
+    }
+    if lines.is_empty() {
+        "  no differences".to_owned()
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn history_report(input: &str, ctx: &Context) -> Result<String> {
+    let (drawing, instructions, first_line) = split_input(input)?;
+    let stacks = parse_stacks(drawing);
+    let crane = crane_from_params(ctx)?;
+    validate(&stacks, instructions, first_line, crane.as_ref())?;
+    let instructions: Vec<Instruction> = instructions
+        .lines()
+        .map(Instruction::parse)
+        .collect::<Result<_>>()?;
+    let total = instructions.len();
+    let mut history = History::new(crane.as_ref(), stacks, instructions);
+
+    let mut output = Vec::new();
+    if let Some(range) = ctx.params.get_opt::<String>("diff")? {
+        let positions: Vec<usize> = range
+            .split(',')
+            .map(|position| position.trim().parse())
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|_| SimpleError::new(format!("Invalid diff '{}', expected a,b", range)))?;
+        let [from, to] = positions[..] else {
+            return Err(Box::new(SimpleError::new(format!(
+                "Invalid diff '{}', expected a,b",
+                range
+            ))));
+        };
+        let before = history.snapshot(from)?;
+        let after = history.snapshot(to)?;
+        output.push(format!(
+            "Changes from after instruction {} to after instruction {}:",
+            from, to
+        ));
+        output.push(diff_stacks(&before, &after));
+        output.push(String::new());
+    }
+
+    history.jump(ctx.params.get("at", total)?)?;
+    history.undo(ctx.params.get("undo", 0)?)?;
+    history.redo(ctx.params.get("redo", 0)?)?;
+    output.push(format!(
+        "After instruction {} of {}:",
+        history.position(),
+        total
+    ));
+    output.push(render_stacks(&history.stacks));
+    Ok(output.join("\n"))
+}
+
+impl Solution for Day05 {
+    fn part1(&self, input: &str, _ctx: &Context) -> Result<String> {
+        let (stacks, _) = rearrange(input, &CrateMover9000)?;
+        Ok(top_crates(&stacks))
+    }
+
+    fn part2(&self, input: &str, _ctx: &Context) -> Result<String> {
+        let (stacks, _) = rearrange(input, &CrateMover9001)?;
+        Ok(top_crates(&stacks))
+    }
+
+    fn modes(&self) -> &'static [&'static str] {
+        &["crane", "draw", "validate", "plan", "history"]
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &[
+            "crane",
+            "capacity",
+            "tops",
+            "target_file",
+            "max_states",
+            "diff",
+            "at",
+            "undo",
+            "redo",
+        ]
+    }
+
+    fn run_mode(&self, mode: &str, input: &str, ctx: &Context) -> Result<String> {
+        match mode {
+            "crane" => {
+                let crane = crane_from_params(ctx)?;
+                let (stacks, stats) = rearrange(input, crane.as_ref())?;
+                Ok(format!(
+                    "Crane: {}\nTop crates: {}\nLifts: {}\nDistance: {}",
+                    crane.name(),
+                    top_crates(&stacks),
+                    stats.lifts,
+                    stats.distance
+                ))
+            }
+            "draw" => {
+                let (stacks, _) = rearrange(input, crane_from_params(ctx)?.as_ref())?;
+                Ok(render_stacks(&stacks))
+            }
+            "validate" => {
+                let (drawing, instructions, first_line) = split_input(input)?;
+                let crane = crane_from_params(ctx)?;
+                let heights = validate(
+                    &parse_stacks(drawing),
+                    instructions,
+                    first_line,
+                    crane.as_ref(),
+                )?;
+                Ok(format!(
+                    "All {} instructions are valid\nFinal stack heights: {}",
+                    instructions.lines().count(),
+                    format_heights(&heights)
+                ))
+            }
+            "plan" => plan_report(input, ctx),
+            "history" => history_report(input, ctx),
+            _ => Err(unsupported_mode(mode, self.modes())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 ";
+
+    fn stacks(columns: &[(i32, &str)]) -> HashMap<i32, Vec<char>> {
+        columns
+            .iter()
+            .map(|(label, crates)| (*label, crates.chars().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_example_exactly() {
+        assert_eq!(render_stacks(&parse_stacks(EXAMPLE)), EXAMPLE);
+    }
+
+    #[test]
+    fn round_trips_puzzle_input() {
+        let input = include_str!("../data/day05.txt");
+        let drawing = input.split("\n\n").next().unwrap();
+        assert_eq!(render_stacks(&parse_stacks(drawing)), drawing);
+    }
+
+    #[test]
+    fn round_trips_empty_stacks() {
+        let state = stacks(&[(1, ""), (2, "AB"), (3, "")]);
+        let drawing = render_stacks(&state);
+        assert_eq!(drawing, "    [B]    \n    [A]    \n 1   2   3 ");
+        assert_eq!(parse_stacks(&drawing), state);
+    }
+
+    #[test]
+    fn round_trips_all_empty() {
+        let state = stacks(&[(1, ""), (2, "")]);
+        let drawing = render_stacks(&state);
+        assert_eq!(drawing, " 1   2 ");
+        assert_eq!(parse_stacks(&drawing), state);
+    }
+
+    #[test]
+    fn round_trips_wide_labels() {
+        let columns: Vec<(i32, String)> = (1..=12)
+            .map(|label| {
+                let crates = (0..label % 4).map(|i| (b'A' + (label + i) as u8) as char);
+                (label, crates.collect())
+            })
+            .collect();
+        let columns: Vec<(i32, &str)> = columns
+            .iter()
+            .map(|(label, crates)| (*label, crates.as_str()))
+            .collect();
+        let state = stacks(&columns);
+        let drawing = render_stacks(&state);
+        assert!(drawing.ends_with(" 9   10  11  12"));
+        assert_eq!(parse_stacks(&drawing), state);
+    }
+
+    #[test]
+    fn plans_replay_through_process_line() {
+        let stacks = parse_stacks(EXAMPLE);
+        let cancel = CancellationToken::default();
+        for crane in [&CrateMover9000 as &dyn Crane, &CrateMover9001] {
+            let planner = Planner {
+                crane,
+                labels: vec![1, 2, 3],
+                goals: goals_from_tops("CMZ", &[1, 2, 3]).unwrap(),
+                max_states: 10_000,
+                cancel: &cancel,
+            };
+            let plan = planner.plan(&stacks).unwrap();
+            assert!(plan.minimal);
+            let mut replayed = stacks.clone();
+            let mut stats = CraneStats::default();
+            for instruction in &plan.instructions {
+                process_line(&mut replayed, &instruction.to_string(), crane, &mut stats).unwrap();
+            }
+            assert_eq!(top_crates(&replayed), "CMZ");
+        }
+    }
+
+    #[test]
+    fn history_matches_replay_and_undoes() {
+        let input = include_str!("../data/day05.txt");
+        let (drawing, lines, _) = split_input(input).unwrap();
+        let instructions: Vec<Instruction> = lines
+            .lines()
+            .map(|line| Instruction::parse(line).unwrap())
+            .collect();
+        let adjacent = AdjacentOnly { capacity: 2 };
+        for crane in [&CrateMover9000 as &dyn Crane, &CrateMover9001, &adjacent] {
+            let initial = parse_stacks(drawing);
+            let mut history = History::new(crane, initial.clone(), instructions.clone());
+            let mut replayed = initial.clone();
+            let mut stats = CraneStats::default();
+            for (position, line) in lines.lines().take(50).enumerate() {
+                assert_eq!(history.snapshot(position).unwrap(), replayed);
+                process_line(&mut replayed, line, crane, &mut stats).unwrap();
+            }
+            history.jump(instructions.len()).unwrap();
+            assert_eq!(history.stacks, rearrange(input, crane).unwrap().0);
+            history.undo(instructions.len()).unwrap();
+            assert_eq!(history.stacks, initial);
+            assert!(history.undo(1).is_err());
+        }
+    }
+
+    #[test]
+    fn round_trips_after_moves() {
+        let mut state = parse_stacks(EXAMPLE);
+        let mut stats = CraneStats::default();
+        for line in [
+            "move 1 from 2 to 1",
+            "move 3 from 1 to 3",
+            "move 2 from 2 to 1",
+        ] {
+            process_line(&mut state, line, &CrateMover9000, &mut stats).unwrap();
+        }
+        assert_eq!(parse_stacks(&render_stacks(&state)), state);
+        assert_eq!(top_crates(&state), "MZ");
+    }
+
+    fn invalid(instructions: &str) -> InstructionError {
+        validate(&parse_stacks(EXAMPLE), instructions, 6, &CrateMover9001).unwrap_err()
+    }
+
+    #[test]
+    fn reports_malformed_instructions() {
+        let error = invalid("move 1 from 2 to 1\nmove one from 1 to 2");
+        assert!(matches!(error.reason, InvalidReason::Malformed));
+        assert_eq!(error.line, 7);
+        assert_eq!(error.heights, [(1, 3), (2, 2), (3, 1)]);
+    }
+
+    #[test]
+    fn reports_unknown_labels() {
+        let error = invalid("move 1 from 2 to 1\nmove 1 from 1 to 2\nmove 1 from 1 to 4");
+        assert!(matches!(error.reason, InvalidReason::UnknownLabel(4)));
+        assert_eq!(error.line, 8);
+        assert_eq!(error.heights, [(1, 2), (2, 3), (3, 1)]);
+    }
+
+    #[test]
+    fn reports_same_stack() {
+        let error = invalid("move 2 from 2 to 2");
+        assert!(matches!(error.reason, InvalidReason::SameStack));
+        assert_eq!(error.line, 6);
+        assert_eq!(error.heights, [(1, 2), (2, 3), (3, 1)]);
+    }
+
+    #[test]
+    fn reports_empty_source() {
+        let error = invalid("move 2 from 1 to 3\nmove 5 from 3 to 2");
+        assert!(matches!(
+            error.reason,
+            InvalidReason::EmptySource { available: 3 }
+        ));
+        assert_eq!(error.line, 7);
+        assert_eq!(error.heights, [(1, 0), (2, 3), (3, 3)]);
+        assert!(error
+            .to_string()
+            .starts_with("Line 7 ('move 5 from 3 to 2')"));
+    }
+
+    #[test]
+    fn rejects_label_gaps_for_the_adjacent_crane() {
+        let state = stacks(&[(1, "A"), (2, "B"), (4, "C")]);
+        let adjacent = AdjacentOnly { capacity: 1 };
+        let error = validate(&state, "move 1 from 1 to 2", 5, &adjacent).unwrap_err();
+        assert!(matches!(
+            error.reason,
+            InvalidReason::LabelGap { after: 2, next: 4 }
+        ));
+        assert_eq!(error.line, 3);
+        assert_eq!(error.heights, [(1, 1), (2, 1), (4, 1)]);
+    }
+
+    fn planner<'a>(
+        crane: &'a dyn Crane,
+        labels: &[i32],
+        goals: Vec<StackGoal>,
+        max_states: usize,
+        cancel: &'a CancellationToken,
+    ) -> Planner<'a> {
+        Planner {
+            crane,
+            labels: labels.to_vec(),
+            goals,
+            max_states,
+            cancel,
+        }
+    }
+
+    /// Replays `plan` with `crane` and checks every goal is met at the end.
+    fn assert_reaches(
+        stacks: &HashMap<i32, Vec<char>>,
+        plan: &Plan,
+        crane: &dyn Crane,
+        goals: &[StackGoal],
+    ) {
+        let mut replayed = stacks.clone();
+        let mut stats = CraneStats::default();
+        for instruction in &plan.instructions {
+            process_line(&mut replayed, &instruction.to_string(), crane, &mut stats).unwrap();
+        }
+        let mut labels: Vec<i32> = replayed.keys().copied().collect();
+        labels.sort_unstable();
+        for (label, goal) in labels.iter().zip(goals) {
+            assert!(goal.is_met(&replayed[label]), "stack {}: {:?}", label, goal);
+        }
+    }
+
+    #[test]
+    fn plans_with_the_adjacent_crane() {
+        let stacks = parse_stacks(EXAMPLE);
+        let cancel = CancellationToken::default();
+        let adjacent = AdjacentOnly {
+            capacity: usize::MAX,
+        };
+        let goals = goals_from_tops("ZDN", &[1, 2, 3]).unwrap();
+        let plan = planner(&adjacent, &[1, 2, 3], goals.clone(), 10_000, &cancel)
+            .plan(&stacks)
+            .unwrap();
+        assert!(plan.minimal);
+        // The crane relays N over stack 2, which ends up as it was.
+        assert_eq!(
+            plan.instructions,
+            [Instruction {
+                count: 1,
+                source: 1,
+                dest: 3
+            }]
+        );
+        assert_reaches(&stacks, &plan, &adjacent, &goals);
+    }
+
+    #[test]
+    fn reports_unreachable_targets() {
+        let cancel = CancellationToken::default();
+        // A lone stack can't be reordered.
+        let single = stacks(&[(1, "AB")]);
+        let goals = vec![StackGoal::Crates(vec!['B', 'A'])];
+        assert!(planner(&CrateMover9001, &[1], goals, 100, &cancel)
+            .plan(&single)
+            .is_err());
+        // Not enough crates of the wanted kind.
+        let example = parse_stacks(EXAMPLE);
+        let goals = goals_from_tops("ZZZ", &[1, 2, 3]).unwrap();
+        assert!(planner(&CrateMover9001, &[1, 2, 3], goals, 100, &cancel)
+            .plan(&example)
+            .is_err());
+    }
+
+    #[test]
+    fn constructs_plans_past_the_state_budget() {
+        let input = include_str!("../data/day05.txt");
+        let (drawing, _, _) = split_input(input).unwrap();
+        let stacks = parse_stacks(drawing);
+        let labels: Vec<i32> = (1..=9).collect();
+        let cancel = CancellationToken::default();
+        let adjacent = AdjacentOnly { capacity: 2 };
+        let target = render_stacks(&rearrange(input, &CrateMover9001).unwrap().0);
+        let goal_sets = [
+            goals_from_tops("QNNTGTPFN", &labels).unwrap(),
+            goals_from_tops("---Q---N-", &labels).unwrap(),
+            goals_from_drawing(&target, &labels).unwrap(),
+        ];
+        for goals in goal_sets {
+            for crane in [&CrateMover9000 as &dyn Crane, &CrateMover9001, &adjacent] {
+                let plan = planner(crane, &labels, goals.clone(), 10, &cancel)
+                    .plan(&stacks)
+                    .unwrap();
+                assert!(!plan.minimal);
+                assert_reaches(&stacks, &plan, crane, &goals);
+            }
+        }
+    }
+
+    /// Applies `line` to the example stacks and returns the stacks and counters.
+    fn run_crane(crane: &dyn Crane, line: &str) -> (HashMap<i32, Vec<char>>, usize, i64) {
+        let mut state = parse_stacks(EXAMPLE);
+        let mut stats = CraneStats::default();
+        process_line(&mut state, line, crane, &mut stats).unwrap();
+        (state, stats.lifts, stats.distance)
+    }
+
+    #[test]
+    fn crate_movers_lift_one_or_all() {
+        assert_eq!(
+            run_crane(&CrateMover9000, "move 3 from 2 to 1"),
+            (stacks(&[(1, "ZNDCM"), (2, ""), (3, "P")]), 3, 3)
+        );
+        assert_eq!(
+            run_crane(&CrateMover9001, "move 3 from 2 to 1"),
+            (stacks(&[(1, "ZNMCD"), (2, ""), (3, "P")]), 1, 1)
+        );
+    }
+
+    #[test]
+    fn capacity_limited_crane_splits_lifts() {
+        // The top two crates go first, then the one left behind lands on them.
+        assert_eq!(
+            run_crane(&CapacityLimited { capacity: 2 }, "move 3 from 2 to 1"),
+            (stacks(&[(1, "ZNCDM"), (2, ""), (3, "P")]), 2, 2)
+        );
+        assert_eq!(
+            run_crane(&CapacityLimited { capacity: 3 }, "move 3 from 2 to 1"),
+            (stacks(&[(1, "ZNMCD"), (2, ""), (3, "P")]), 1, 1)
+        );
+        assert_eq!(
+            run_crane(&CapacityLimited { capacity: 2 }, "move 2 from 2 to 3"),
+            (stacks(&[(1, "ZN"), (2, "M"), (3, "PCD")]), 1, 1)
+        );
+    }
+
+    #[test]
+    fn adjacent_crane_relays_through_every_stack() {
+        // Stack 2 holds the crates for a moment but ends up unchanged.
+        let unlimited = AdjacentOnly {
+            capacity: usize::MAX,
+        };
+        assert_eq!(
+            run_crane(&unlimited, "move 2 from 1 to 3"),
+            (stacks(&[(1, ""), (2, "MCD"), (3, "PZN")]), 2, 2)
+        );
+        assert_eq!(
+            run_crane(&AdjacentOnly { capacity: 1 }, "move 2 from 1 to 3"),
+            (stacks(&[(1, ""), (2, "MCD"), (3, "PNZ")]), 4, 4)
+        );
+        assert_eq!(
+            run_crane(&AdjacentOnly { capacity: 2 }, "move 3 from 2 to 1"),
+            (stacks(&[(1, "ZNCDM"), (2, ""), (3, "P")]), 2, 2)
+        );
+    }
+
+    #[test]
+    fn missing_crates_are_an_error() {
+        let initial = stacks(&[(1, "ZN"), (2, "MCD")]);
+        let too_many = Instruction {
+            count: 5,
+            source: 2,
+            dest: 1,
+        };
+        let mut history = History::new(&CrateMover9001, initial.clone(), vec![too_many]);
+        assert!(history.step_forward().is_err());
+        assert_eq!(history.position(), 0);
+        assert_eq!(history.stacks, initial);
+    }
+
+    #[test]
+    fn other_cranes_cross_label_gaps() {
+        let input =
+            "[A]     [C]\n[D] [B] [E]\n 1   2   4 \n\nmove 1 from 4 to 1\nmove 2 from 1 to 2";
+        let ctx = Context::default();
+        assert_eq!(Day05 {}.part1(input, &ctx).unwrap(), "DAE");
+        assert_eq!(Day05 {}.part2(input, &ctx).unwrap(), "DCE");
+        let mut adjacent = Context::default();
+        adjacent.params.insert("crane", "adjacent");
+        let error = Day05 {}
+            .run_mode("crane", input, &adjacent)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("stack labels jump from 2 to 4"), "{}", error);
+    }
 }
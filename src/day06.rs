@@ -1,45 +1,317 @@
-use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fmt::Write;
 
 use crate::solution::*;
+use simple_error::SimpleError;
 
 pub struct Day06 {}
 
-fn check_distinct(chars: &[char]) -> bool {
-    let mut ctr: HashSet<char> = HashSet::new();
-    for c in chars.iter() {
-        if ctr.contains(c) {
-            return false;
-        } else {
-            ctr.insert(*c);
+const PACKET_MARKER_LEN: usize = 4;
+const MESSAGE_MARKER_LEN: usize = 14;
+
+/// Watches a byte stream for `k` consecutive distinct bytes. Keeps a count per
+/// byte value and of how many values occur more than once in the window, so
+/// each byte costs O(1).
+struct MarkerDetector {
+    k: usize,
+    window: VecDeque<u8>,
+    counts: [usize; 256],
+    duplicated: usize,
+}
+
+impl MarkerDetector {
+    fn new(k: usize) -> Self {
+        Self {
+            k,
+            // More than 256 bytes can't all be distinct, so a longer window
+            // never matches and isn't worth reserving room for.
+            window: VecDeque::with_capacity(k.min(256) + 1),
+            counts: [0; 256],
+            duplicated: 0,
+        }
+    }
+
+    /// Adds a byte, returning true if it completes a marker. The window then
+    /// starts over, so consecutive markers never share bytes.
+    fn push(&mut self, byte: u8) -> bool {
+        self.window.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.duplicated += 1;
+        }
+        if self.window.len() > self.k {
+            let old = self.window.pop_front().unwrap() as usize;
+            if self.counts[old] == 2 {
+                self.duplicated -= 1;
+            }
+            self.counts[old] -= 1;
+        }
+        if self.window.len() == self.k && self.duplicated == 0 {
+            for old in self.window.drain(..) {
+                self.counts[old as usize] -= 1;
+            }
+            return true;
         }
+        false
     }
-    true
 }
 
-impl Solution for Day06 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 06 - Part 1");
-        let input = std::fs::read_to_string("data/day06.txt")?;
-        let characters: Vec<char> = input.chars().collect();
-        for i in 3..characters.len() {
-            if check_distinct(&characters[i - 3..i + 1]) {
-                println!("Start of packet marker found at: {}", i + 1);
-                break;
+/// The number of bytes read when the last `k` bytes are first all different,
+/// or `None` if that never happens.
+fn find_marker(data: &[u8], k: usize) -> Option<usize> {
+    if k == 0 {
+        return Some(0);
+    }
+    let mut detector = MarkerDetector::new(k);
+    data.iter()
+        .position(|byte| detector.push(*byte))
+        .map(|idx| idx + 1)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkerKind {
+    Packet,
+    Message,
+}
+
+impl MarkerKind {
+    fn name(&self) -> &'static str {
+        match self {
+            MarkerKind::Packet => "start-of-packet",
+            MarkerKind::Message => "start-of-message",
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            MarkerKind::Packet => PACKET_MARKER_LEN,
+            MarkerKind::Message => MESSAGE_MARKER_LEN,
+        }
+    }
+}
+
+/// A marker found in the stream. `end` is the offset just past its last byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MarkerEvent {
+    kind: MarkerKind,
+    end: usize,
+}
+
+/// Finds every start-of-packet and start-of-message marker in a stream that
+/// arrives in chunks of any size. The two kinds are tracked independently.
+struct StreamDecoder {
+    offset: usize,
+    detectors: Vec<(MarkerKind, MarkerDetector)>,
+}
+
+impl StreamDecoder {
+    fn new() -> Self {
+        Self {
+            offset: 0,
+            detectors: [MarkerKind::Packet, MarkerKind::Message]
+                .into_iter()
+                .map(|kind| (kind, MarkerDetector::new(kind.len())))
+                .collect(),
+        }
+    }
+
+    /// Consumes the next chunk, returning the markers it completes in stream
+    /// order.
+    fn feed(&mut self, chunk: &[u8]) -> Vec<MarkerEvent> {
+        let mut events = Vec::new();
+        for byte in chunk {
+            self.offset += 1;
+            for (kind, detector) in self.detectors.iter_mut() {
+                if detector.push(*byte) {
+                    events.push(MarkerEvent {
+                        kind: *kind,
+                        end: self.offset,
+                    });
+                }
             }
         }
-        Ok(())
+        events
     }
 
-    fn part2(&self) -> Result<()> {
-        println!("Day 06 - Part 2");
-        let input = std::fs::read_to_string("data/day06.txt")?;
-        let characters: Vec<char> = input.chars().collect();
-        for i in 13..characters.len() {
-            if check_distinct(&characters[i - 13..i + 1]) {
-                println!("Start of message marker found at: {}", i + 1);
-                break;
+    /// Bytes consumed so far.
+    fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// The bytes after a marker, up to the start of the next marker of the same
+/// kind or the end of the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Frame {
+    marker: MarkerEvent,
+    start: usize,
+    len: usize,
+}
+
+fn frames(events: &[MarkerEvent], kind: MarkerKind, stream_len: usize) -> Vec<Frame> {
+    let markers: Vec<MarkerEvent> = events
+        .iter()
+        .filter(|event| event.kind == kind)
+        .copied()
+        .collect();
+    markers
+        .iter()
+        .enumerate()
+        .map(|(idx, marker)| {
+            let end = markers
+                .get(idx + 1)
+                .map_or(stream_len, |next| next.end - kind.len());
+            Frame {
+                marker: *marker,
+                start: marker.end,
+                len: end - marker.end,
             }
+        })
+        .collect()
+}
+
+fn frame_report(input: &str, chunk_size: usize) -> Result<String> {
+    if chunk_size == 0 {
+        return Err(Box::new(SimpleError::new(
+            "Parameter 'chunk' must be at least 1",
+        )));
+    }
+    let mut decoder = StreamDecoder::new();
+    let mut events = Vec::new();
+    for chunk in input.trim_end().as_bytes().chunks(chunk_size) {
+        events.extend(decoder.feed(chunk));
+    }
+    let stream_len = decoder.offset();
+
+    let mut output = String::new();
+    let _ = writeln!(output, "Stream: {} bytes", stream_len);
+    for kind in [MarkerKind::Packet, MarkerKind::Message] {
+        let frames = frames(&events, kind, stream_len);
+        let _ = writeln!(
+            output,
+            "\n{} markers ({} bytes): {}",
+            kind.name(),
+            kind.len(),
+            frames.len()
+        );
+        for (idx, frame) in frames.iter().enumerate() {
+            let _ = writeln!(
+                output,
+                "  #{} marker {}..{}, frame {}..{} ({} bytes)",
+                idx + 1,
+                frame.marker.end - kind.len(),
+                frame.marker.end,
+                frame.start,
+                frame.start + frame.len,
+                frame.len
+            );
+        }
+    }
+    Ok(output.trim_end().to_owned())
+}
+
+fn marker_answer(input: &str, kind: MarkerKind) -> Result<String> {
+    match find_marker(input.trim_end().as_bytes(), kind.len()) {
+        Some(position) => Ok(position.to_string()),
+        None => Err(Box::new(SimpleError::new(format!(
+            "No {} marker ({} distinct characters) found",
+            kind.name(),
+            kind.len()
+        )))),
+    }
+}
+
+impl Solution for Day06 {
+    fn part1(&self, input: &str, _ctx: &Context) -> Result<String> {
+        marker_answer(input, MarkerKind::Packet)
+    }
+
+    fn part2(&self, input: &str, _ctx: &Context) -> Result<String> {
+        marker_answer(input, MarkerKind::Message)
+    }
+
+    fn modes(&self) -> &'static [&'static str] {
+        &["frames"]
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["chunk"]
+    }
+
+    fn run_mode(&self, mode: &str, input: &str, ctx: &Context) -> Result<String> {
+        match mode {
+            "frames" => frame_report(input, ctx.params.get("chunk", 4096)?),
+            _ => Err(unsupported_mode(mode, self.modes())),
         }
-        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_puzzle_examples() {
+        let examples = [
+            ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+            ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+        ];
+        for (data, packet, message) in examples {
+            assert_eq!(find_marker(data.as_bytes(), 4), Some(packet));
+            assert_eq!(find_marker(data.as_bytes(), 14), Some(message));
+        }
+    }
+
+    #[test]
+    fn window_of_one_is_first_byte() {
+        assert_eq!(find_marker(b"aaaa", 1), Some(1));
+        assert_eq!(find_marker(b"", 1), None);
+    }
+
+    #[test]
+    fn window_longer_than_input_is_not_found() {
+        assert_eq!(find_marker(b"abc", 4), None);
+        assert_eq!(find_marker(b"abc", 3), Some(3));
+        assert_eq!(find_marker(b"", 5), None);
+        assert_eq!(find_marker(b"abc", usize::MAX), None);
+    }
+
+    #[test]
+    fn repeated_input_is_not_found() {
+        assert_eq!(find_marker(b"abababababab", 3), None);
+    }
+
+    #[test]
+    fn handles_arbitrary_bytes() {
+        assert_eq!(find_marker(&[0, 0, 255, 0, 128, 255, 7], 3), Some(5));
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(find_marker(&all, 256), Some(256));
+    }
+
+    #[test]
+    fn streaming_matches_single_chunk() {
+        let data = include_str!("../data/day06.txt").trim_end().as_bytes();
+        let expected = StreamDecoder::new().feed(data);
+        assert!(expected.len() > 2);
+        for chunk_size in [1, 3, 13, 14, 1000] {
+            let mut decoder = StreamDecoder::new();
+            let events: Vec<MarkerEvent> = data
+                .chunks(chunk_size)
+                .flat_map(|chunk| decoder.feed(chunk))
+                .collect();
+            assert_eq!(events, expected);
+        }
+    }
+
+    #[test]
+    fn frames_run_until_next_marker() {
+        let data = b"aabcdaaaawxyzqq";
+        let events = StreamDecoder::new().feed(data);
+        let packets = frames(&events, MarkerKind::Packet, data.len());
+        let spans: Vec<(usize, usize)> = packets.iter().map(|f| (f.start, f.len)).collect();
+        assert_eq!(spans, vec![(5, 3), (12, 3)]);
     }
 }
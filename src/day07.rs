@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
 use simple_error::SimpleError;
 
 use crate::solution::*;
@@ -14,18 +17,12 @@ enum Command {
 impl Command {
     fn from_line(line: &str) -> Option<Self> {
         let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts[1] == "cd" {
-            if parts[2] == "/" {
-                Some(Command::ChangeDirectoryToRoot)
-            } else if parts[2] == ".." {
-                Some(Command::ChangeDirectoryUp)
-            } else {
-                Some(Command::ChangeDirectoryDown(parts[2].to_owned()))
-            }
-        } else if parts[1] == "ls" {
-            Some(Command::List)
-        } else {
-            None
+        match parts.as_slice() {
+            ["$", "cd", "/"] => Some(Command::ChangeDirectoryToRoot),
+            ["$", "cd", ".."] => Some(Command::ChangeDirectoryUp),
+            ["$", "cd", name] => Some(Command::ChangeDirectoryDown((*name).to_owned())),
+            ["$", "ls"] => Some(Command::List),
+            _ => None,
         }
     }
 }
@@ -38,14 +35,10 @@ enum Content {
 impl Content {
     pub fn from_line(line: &str) -> Option<Self> {
         let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts[0] == "dir" {
-            Some(Content::Directory(parts[1].to_owned()))
-        } else {
-            if let Ok(size) = parts[0].parse::<usize>() {
-                Some(Content::File(parts[1].to_owned(), size))
-            } else {
-                None
-            }
+        match parts.as_slice() {
+            ["dir", name] => Some(Content::Directory((*name).to_owned())),
+            [size, name] => Some(Content::File((*name).to_owned(), size.parse().ok()?)),
+            _ => None,
         }
     }
 }
@@ -56,58 +49,274 @@ enum LineType {
     Output(Content),
 }
 
+/// Index of a directory in a [`Filesystem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NodeId(usize);
+
 #[derive(Debug)]
 struct Directory {
     pub name: String,
-    pub sub_directories: Vec<Box<Directory>>,
-    pub files: Vec<File>,
-    pub parent: Option<*mut Directory>,
+    pub parent: Option<NodeId>,
+    pub sub_directories: BTreeMap<String, NodeId>,
+    /// File sizes by name.
+    pub files: BTreeMap<String, usize>,
 }
 
 impl Directory {
-    pub fn new(name: &str) -> Self {
+    fn new(name: &str, parent: Option<NodeId>) -> Self {
         Self {
             name: name.to_owned(),
-            sub_directories: Vec::new(),
-            files: Vec::new(),
-            parent: None,
+            parent,
+            sub_directories: BTreeMap::new(),
+            files: BTreeMap::new(),
         }
     }
-    pub fn new_with_parent(name: &str, parent: &mut Directory) -> Self {
+}
+
+/// The directory tree, stored as an arena: directories refer to each other by
+/// [`NodeId`] and the root is always the first node.
+#[derive(Debug)]
+struct Filesystem {
+    nodes: Vec<Directory>,
+}
+
+impl Filesystem {
+    const ROOT: NodeId = NodeId(0);
+
+    fn new() -> Self {
         Self {
-            name: name.to_owned(),
-            sub_directories: Vec::new(),
-            files: Vec::new(),
-            parent: Some(parent),
+            nodes: vec![Directory::new("/", None)],
+        }
+    }
+
+    fn get(&self, id: NodeId) -> &Directory {
+        &self.nodes[id.0]
+    }
+
+    /// The subdirectory `name` of `parent`, created if it doesn't exist yet.
+    fn add_directory(&mut self, parent: NodeId, name: &str) -> NodeId {
+        if let Some(id) = self.get(parent).sub_directories.get(name) {
+            return *id;
+        }
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Directory::new(name, Some(parent)));
+        self.nodes[parent.0]
+            .sub_directories
+            .insert(name.to_owned(), id);
+        id
+    }
+
+    /// Records a file, replacing any earlier listing of it.
+    fn add_file(&mut self, parent: NodeId, name: &str, size: usize) {
+        self.nodes[parent.0].files.insert(name.to_owned(), size);
+    }
+
+    /// The absolute path of a directory, e.g. `/a/e`.
+    fn path(&self, id: NodeId) -> String {
+        let mut names = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.get(current).parent {
+            names.push(self.get(current).name.as_str());
+            current = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    /// The entry at `path`. Paths are taken from the root whether or not they
+    /// start with `/`, and may contain `.` and `..`.
+    fn resolve(&self, path: &str) -> Option<Entry> {
+        let components: Vec<&str> = path
+            .split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect();
+        let mut cwd = Self::ROOT;
+        for (idx, part) in components.iter().enumerate() {
+            let directory = self.get(cwd);
+            if *part == ".." {
+                cwd = directory.parent?;
+            } else if let Some(subdir) = directory.sub_directories.get(*part) {
+                cwd = *subdir;
+            } else if idx + 1 == components.len() {
+                let size = *directory.files.get(*part)?;
+                return Some(Entry::File { parent: cwd, size });
+            } else {
+                return None;
+            }
+        }
+        Some(Entry::Directory(cwd))
+    }
+
+    /// The recursive size of every directory, indexed by node. Children are
+    /// always added after their parent, so one backwards pass is enough.
+    fn directory_sizes(&self) -> Vec<usize> {
+        let mut sizes: Vec<usize> = self
+            .nodes
+            .iter()
+            .map(|directory| directory.files.values().sum())
+            .collect();
+        for (idx, directory) in self.nodes.iter().enumerate().rev() {
+            if let Some(parent) = directory.parent {
+                sizes[parent.0] += sizes[idx];
+            }
+        }
+        sizes
+    }
+
+    /// Every entry under `from` (itself included) with its full path: each
+    /// directory is followed by its files and then its subdirectories, all in
+    /// name order.
+    fn walk(&self, from: NodeId) -> Vec<(String, Entry)> {
+        let mut entries = Vec::new();
+        let mut pending = vec![from];
+        while let Some(id) = pending.pop() {
+            let path = self.path(id);
+            let prefix = if id == Self::ROOT { "" } else { path.as_str() };
+            let directory = self.get(id);
+            let files: Vec<(String, Entry)> = directory
+                .files
+                .iter()
+                .map(|(name, size)| {
+                    let entry = Entry::File {
+                        parent: id,
+                        size: *size,
+                    };
+                    (format!("{}/{}", prefix, name), entry)
+                })
+                .collect();
+            entries.push((path, Entry::Directory(id)));
+            entries.extend(files);
+            pending.extend(directory.sub_directories.values().rev());
         }
+        entries
     }
 }
 
-#[derive(Debug)]
-struct File {
-    pub _name: String,
-    pub size: usize,
+/// Something a path can refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Directory(NodeId),
+    File { parent: NodeId, size: usize },
+}
+
+impl Entry {
+    /// The file size, or the recursive size for a directory.
+    fn size(&self, directory_sizes: &[usize]) -> usize {
+        match self {
+            Entry::Directory(id) => directory_sizes[id.0],
+            Entry::File { size, .. } => *size,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Entry::Directory(_) => "dir",
+            Entry::File { .. } => "file",
+        }
+    }
+}
+
+/// Shell-style matching where `*` is any run of characters and `?` any one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and how much of `name` it has consumed.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// One condition of a `find` query.
+#[derive(Debug, PartialEq, Eq)]
+enum Filter {
+    Name(String),
+    Type(&'static str),
+    Size(std::cmp::Ordering, bool, usize),
+}
+
+impl Filter {
+    /// Parses `name=GLOB`, `type=f|d` or `size` compared with `<`, `<=`, `=`,
+    /// `>=` or `>` to a number of bytes.
+    fn parse(input: &str) -> Result<Self> {
+        use std::cmp::Ordering::*;
+        if let Some(pattern) = input.strip_prefix("name=") {
+            return Ok(Filter::Name(pattern.to_owned()));
+        }
+        if let Some(kind) = input.strip_prefix("type=") {
+            return match kind {
+                "f" => Ok(Filter::Type("file")),
+                "d" => Ok(Filter::Type("dir")),
+                _ => Err(Box::new(SimpleError::new(format!(
+                    "Invalid type '{}', expected 'f' or 'd'",
+                    kind
+                )))),
+            };
+        }
+        if let Some(rest) = input.strip_prefix("size") {
+            for (op, ordering, or_equal) in [
+                ("<=", Less, true),
+                (">=", Greater, true),
+                ("<", Less, false),
+                (">", Greater, false),
+                ("=", Equal, true),
+            ] {
+                if let Some(value) = rest.strip_prefix(op) {
+                    return Ok(Filter::Size(ordering, or_equal, value.parse()?));
+                }
+            }
+        }
+        Err(Box::new(SimpleError::new(format!(
+            "Invalid filter '{}', expected name=GLOB, type=f|d or size<N, size>=N, ...",
+            input
+        ))))
+    }
+
+    fn matches(&self, path: &str, entry: &Entry, size: usize) -> bool {
+        match self {
+            Filter::Name(pattern) => glob_match(pattern, path.rsplit('/').next().unwrap_or("")),
+            Filter::Type(kind) => entry.kind() == *kind,
+            Filter::Size(ordering, or_equal, value) => {
+                let compared = size.cmp(value);
+                compared == *ordering || (*or_equal && compared == std::cmp::Ordering::Equal)
+            }
+        }
+    }
 }
 
 fn is_command(line: &str) -> bool {
-    line.chars().next().unwrap() == '$'
+    line.starts_with('$')
 }
 
-fn process_lines(lines: &Vec<&str>) -> std::result::Result<Vec<LineType>, SimpleError> {
+fn process_lines(lines: &[&str]) -> std::result::Result<Vec<LineType>, SimpleError> {
     let processed: std::result::Result<Vec<LineType>, SimpleError> = lines
         .iter()
         .map(|line| {
             if is_command(line) {
                 if let Some(command) = Command::from_line(line) {
-                    return Ok(LineType::Input(command));
+                    Ok(LineType::Input(command))
                 } else {
-                    return Err(SimpleError::new(format!("Invalid command: {}", line)));
+                    Err(SimpleError::new(format!("Invalid command: {}", line)))
                 }
             } else {
                 if let Some(content) = Content::from_line(line) {
-                    return Ok(LineType::Output(content));
+                    Ok(LineType::Output(content))
                 } else {
-                    return Err(SimpleError::new(format!("Invalid output: {}", line)));
+                    Err(SimpleError::new(format!("Invalid output: {}", line)))
                 }
             }
         })
@@ -116,123 +325,528 @@ fn process_lines(lines: &Vec<&str>) -> std::result::Result<Vec<LineType>, Simple
 }
 
 const CUTOFF: usize = 100000;
-fn accumulate_directory_sizes(
-    node: &Directory,
-    sizes: &mut Vec<usize>,
-    cutoff: Option<usize>,
-) -> usize {
-    let this_size = node
-        .files
-        .iter()
-        .map(|file| file.size)
-        .reduce(|acc, x| acc + x)
-        .unwrap_or(0);
-    let sub_sizes = node
-        .sub_directories
-        .iter()
-        .map(|subdir| accumulate_directory_sizes(subdir, sizes, cutoff))
-        .reduce(|acc, x| acc + x)
-        .unwrap_or(0);
+const DISK_SIZE: usize = 70000000;
+const REQUIRED_SIZE: usize = 30000000;
+
+fn build_filesystem(input: &str) -> Result<Filesystem> {
+    let lines: Vec<&str> = input.lines().collect();
+    replay_commands(process_lines(&lines)?)
+}
+
+/// The directory at `path`, for queries that search below it.
+fn resolve_directory(fs: &Filesystem, path: &str) -> Result<NodeId> {
+    match fs.resolve(path) {
+        Some(Entry::Directory(id)) => Ok(id),
+        Some(Entry::File { .. }) => Err(Box::new(SimpleError::new(format!(
+            "'{}' is a file, not a directory",
+            path
+        )))),
+        None => Err(Box::new(SimpleError::new(format!(
+            "No such path: {}",
+            path
+        )))),
+    }
+}
 
-    let combined_size = this_size + sub_sizes;
-    if let Some(cutoff) = cutoff {
-        if combined_size <= cutoff {
-            sizes.push(combined_size);
+/// Runs one query against the filesystem:
+///
+/// * `resolve PATH` - what is at `PATH` and its size.
+/// * `walk [PATH]` - every file and directory below `PATH` (default `/`).
+/// * `find [PATH] FILTER...` - the entries below `PATH` matching every filter;
+///   see [`Filter::parse`].
+/// * `du [PATH]` - every directory below `PATH` with its recursive size.
+fn run_query(fs: &Filesystem, sizes: &[usize], query: &str) -> Result<Vec<String>> {
+    let mut words = query.split_whitespace();
+    let command = words.next().unwrap_or("");
+    let mut args: Vec<&str> = words.collect();
+    let listing = |entries: Vec<(String, Entry)>| -> Vec<String> {
+        entries
+            .into_iter()
+            .map(|(path, entry)| format!("{:>10} {:<4} {}", entry.size(sizes), entry.kind(), path))
+            .collect()
+    };
+    match command {
+        "resolve" => {
+            let [path] = args[..] else {
+                return Err(Box::new(SimpleError::new("Usage: resolve PATH")));
+            };
+            match fs.resolve(path) {
+                Some(entry) => Ok(vec![format!(
+                    "{}: {}, {} bytes",
+                    path,
+                    entry.kind(),
+                    entry.size(sizes)
+                )]),
+                None => Err(Box::new(SimpleError::new(format!(
+                    "No such path: {}",
+                    path
+                )))),
+            }
+        }
+        "walk" | "du" | "find" => {
+            let root = match args.first() {
+                Some(path) if path.starts_with('/') => {
+                    let id = resolve_directory(fs, path)?;
+                    args.remove(0);
+                    id
+                }
+                _ => Filesystem::ROOT,
+            };
+            let filters: Vec<Filter> = match command {
+                "find" => args
+                    .iter()
+                    .map(|arg| Filter::parse(arg))
+                    .collect::<Result<_>>()?,
+                _ if !args.is_empty() => {
+                    return Err(Box::new(SimpleError::new(format!(
+                        "Usage: {} [PATH]",
+                        command
+                    ))))
+                }
+                "du" => vec![Filter::Type("dir")],
+                _ => Vec::new(),
+            };
+            let entries = fs
+                .walk(root)
+                .into_iter()
+                .filter(|(path, entry)| {
+                    filters
+                        .iter()
+                        .all(|filter| filter.matches(path, entry, entry.size(sizes)))
+                })
+                .collect();
+            Ok(listing(entries))
+        }
+        _ => Err(Box::new(SimpleError::new(format!(
+            "Unknown query '{}', expected resolve, walk, find or du",
+            command
+        )))),
+    }
+}
+
+/// Runs the `;`-separated queries in `queries` and reports each one's results.
+fn query_report(input: &str, queries: &str) -> Result<String> {
+    let fs = build_filesystem(input)?;
+    let sizes = fs.directory_sizes();
+    let mut output = String::new();
+    for query in queries.split(';').map(str::trim).filter(|q| !q.is_empty()) {
+        let results = run_query(&fs, &sizes, query)?;
+        let _ = writeln!(output, "> {} ({} results)", query, results.len());
+        for line in results {
+            let _ = writeln!(output, "{}", line);
         }
+    }
+    Ok(output.trim_end().to_owned())
+}
+
+/// A size the way `du -h` prints it: bytes below 1024, otherwise in the
+/// largest fitting binary unit, rounded up to one decimal under 10.
+fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    value /= 1024.0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 && (value * 10.0).ceil() < 100.0 {
+        format!("{:.1}{}", (value * 10.0).ceil() / 10.0, UNITS[unit])
     } else {
-        sizes.push(combined_size);
+        format!("{:.0}{}", value.ceil(), UNITS[unit])
+    }
+}
+
+/// Appends the entries below `id` to a `tree`-style listing, stopping after
+/// `depth` more levels if given.
+fn render_tree(
+    fs: &Filesystem,
+    sizes: &[usize],
+    id: NodeId,
+    prefix: &str,
+    depth: Option<usize>,
+    output: &mut String,
+) {
+    if depth == Some(0) {
+        return;
+    }
+    let directory = fs.get(id);
+    let mut children: Vec<(&str, Entry)> = directory
+        .files
+        .iter()
+        .map(|(name, size)| {
+            let entry = Entry::File {
+                parent: id,
+                size: *size,
+            };
+            (name.as_str(), entry)
+        })
+        .chain(
+            directory
+                .sub_directories
+                .iter()
+                .map(|(name, subdir)| (name.as_str(), Entry::Directory(*subdir))),
+        )
+        .collect();
+    children.sort_by_key(|(name, _)| *name);
+    for (idx, (name, entry)) in children.iter().enumerate() {
+        let last = idx + 1 == children.len();
+        let _ = writeln!(
+            output,
+            "{}{}{} ({})",
+            prefix,
+            if last { "└── " } else { "├── " },
+            name,
+            entry.size(sizes)
+        );
+        if let Entry::Directory(subdir) = entry {
+            let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            render_tree(fs, sizes, *subdir, &prefix, depth.map(|d| d - 1), output);
+        }
+    }
+}
+
+fn tree_report(input: &str, depth: Option<usize>) -> Result<String> {
+    let fs = build_filesystem(input)?;
+    let sizes = fs.directory_sizes();
+    let mut output = String::new();
+    let _ = writeln!(output, "/ ({})", sizes[Filesystem::ROOT.0]);
+    render_tree(&fs, &sizes, Filesystem::ROOT, "", depth, &mut output);
+    Ok(output.trim_end().to_owned())
+}
+
+/// Every directory with its human-readable size, largest first.
+fn du_report(input: &str) -> Result<String> {
+    let fs = build_filesystem(input)?;
+    let sizes = fs.directory_sizes();
+    let mut directories: Vec<(usize, String)> = (0..fs.nodes.len())
+        .map(|idx| (sizes[idx], fs.path(NodeId(idx))))
+        .collect();
+    directories.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    let mut output = String::new();
+    for (size, path) in directories {
+        let _ = writeln!(output, "{:<6}{}", human_size(size), path);
     }
-    combined_size
+    Ok(output.trim_end().to_owned())
+}
+
+/// The `count` largest directories and the share of the disk each one takes.
+/// Nested directories are counted in their parents too, so the shares add up
+/// to more than the space used.
+fn top_report(input: &str, count: usize, disk_size: usize) -> Result<String> {
+    if disk_size == 0 {
+        return Err(Box::new(SimpleError::new(
+            "Parameter 'disk_size' must be at least 1",
+        )));
+    }
+    let fs = build_filesystem(input)?;
+    let sizes = fs.directory_sizes();
+    let used = sizes[Filesystem::ROOT.0];
+    let percent = |size: usize| 100.0 * size as f64 / disk_size as f64;
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "Disk: {} used of {} ({:.1}%), {} free",
+        human_size(used),
+        human_size(disk_size),
+        percent(used),
+        human_size(disk_size.saturating_sub(used))
+    );
+    let mut ranked: Vec<usize> = (0..fs.nodes.len()).collect();
+    ranked.sort_by_key(|idx| std::cmp::Reverse(sizes[*idx]));
+    for (rank, idx) in ranked.into_iter().take(count).enumerate() {
+        let share = percent(sizes[idx]);
+        let _ = writeln!(
+            output,
+            "{:>3}. {:>6} {:>5.1}% [{:<20}] {}",
+            rank + 1,
+            human_size(sizes[idx]),
+            share,
+            "#".repeat(((share / 5.0).round() as usize).min(20)),
+            fs.path(NodeId(idx))
+        );
+    }
+    Ok(output.trim_end().to_owned())
 }
 
 impl Solution for Day07 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 07 - Part 1");
-        let input = std::fs::read_to_string("data/day07.txt")?;
+    fn part1(&self, input: &str, ctx: &Context) -> Result<String> {
+        let cutoff = ctx.params.get("cutoff", CUTOFF)?;
         let lines: Vec<&str> = input.lines().collect();
         let processed = process_lines(&lines)?;
 
-        let root = replay_commands(processed)?;
+        let fs = replay_commands(processed)?;
 
-        let mut sizes: Vec<usize> = Vec::new();
-        let _ = accumulate_directory_sizes(&root, &mut sizes, Some(CUTOFF));
-        let combined_sizes = sizes.into_iter().reduce(|acc, x| acc + x).unwrap_or(0);
-        println!("Total size: {}", combined_sizes);
-        Ok(())
+        let combined_sizes: usize = fs
+            .directory_sizes()
+            .into_iter()
+            .filter(|size| *size <= cutoff)
+            .sum();
+        Ok(combined_sizes.to_string())
     }
-    fn part2(&self) -> Result<()> {
-        const DISK_SIZE: usize = 70000000;
-        const REQUIRED_SIZE: usize = 30000000;
-        println!("Day 07 - Part 2");
-        let input = std::fs::read_to_string("data/day07.txt")?;
+    fn part2(&self, input: &str, ctx: &Context) -> Result<String> {
+        let disk_size = ctx.params.get("disk_size", DISK_SIZE)?;
+        let required_size = ctx.params.get("required_size", REQUIRED_SIZE)?;
         let lines: Vec<&str> = input.lines().collect();
         let processed = process_lines(&lines)?;
-        let root = replay_commands(processed)?;
+        let fs = replay_commands(processed)?;
 
-        let mut sizes: Vec<usize> = Vec::new();
-        let used_size = accumulate_directory_sizes(&root, &mut sizes, None);
-        if (DISK_SIZE - used_size) > REQUIRED_SIZE {
-            println!("Enough space available, no need to delete anything.");
-            return Ok(());
+        let sizes = fs.directory_sizes();
+        let used_size = sizes[Filesystem::ROOT.0];
+        let free_size = disk_size.saturating_sub(used_size);
+        if free_size > required_size {
+            // Enough space available, no need to delete anything.
+            Ok(0.to_string())
         } else {
-            let need_to_delete = REQUIRED_SIZE - (DISK_SIZE - used_size);
-            println!("Need to delete at least: {}", need_to_delete);
+            let need_to_delete = required_size - free_size;
             let mut large_enough_directories: Vec<usize> =
                 sizes.into_iter().filter(|x| *x >= need_to_delete).collect();
             large_enough_directories.sort();
-            println!(
-                "Smallest size that can be deleted: {}",
-                large_enough_directories.first().unwrap_or(&0)
-            );
+            Ok(large_enough_directories.first().unwrap_or(&0).to_string())
+        }
+    }
+
+    fn modes(&self) -> &'static [&'static str] {
+        &["query", "tree", "du", "top"]
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &[
+            "cutoff",
+            "disk_size",
+            "required_size",
+            "query",
+            "depth",
+            "top",
+        ]
+    }
+
+    fn run_mode(&self, mode: &str, input: &str, ctx: &Context) -> Result<String> {
+        match mode {
+            "query" => query_report(input, &ctx.params.get("query", "du".to_owned())?),
+            "tree" => tree_report(input, ctx.params.get_opt("depth")?),
+            "du" => du_report(input),
+            "top" => top_report(
+                input,
+                ctx.params.get("top", 10)?,
+                ctx.params.get("disk_size", DISK_SIZE)?,
+            ),
+            _ => Err(unsupported_mode(mode, self.modes())),
         }
-        Ok(())
     }
 }
 
-fn replay_commands(processed: Vec<LineType>) -> Result<Directory> {
-    let mut root = Directory::new("/");
-    let mut cwd = &mut root;
+fn replay_commands(processed: Vec<LineType>) -> Result<Filesystem> {
+    let mut fs = Filesystem::new();
+    let mut cwd = Filesystem::ROOT;
     for line in processed {
         match line {
             LineType::Input(Command::ChangeDirectoryToRoot) => {
-                cwd = &mut root;
+                cwd = Filesystem::ROOT;
             }
             LineType::Input(Command::ChangeDirectoryUp) => {
-                if let Some(parent) = cwd.parent {
-                    cwd = unsafe { &mut *parent };
+                if let Some(parent) = fs.get(cwd).parent {
+                    cwd = parent;
                 } else {
                     return Err(Box::new(SimpleError::new(format!(
                         "Current directory '{}' has no parent.",
-                        cwd.name
+                        fs.get(cwd).name
                     ))));
                 }
             }
             LineType::Input(Command::ChangeDirectoryDown(new_dir)) => {
-                if let Some(subdir) = cwd
-                    .sub_directories
-                    .iter_mut()
-                    .find(|subdir| subdir.name == new_dir)
-                {
-                    cwd = &mut *subdir;
+                if let Some(subdir) = fs.get(cwd).sub_directories.get(&new_dir) {
+                    cwd = *subdir;
                 } else {
                     return Err(Box::new(SimpleError::new(format!(
                         "Current directory '{}' has no subdirectory '{}'.",
-                        cwd.name, new_dir
+                        fs.get(cwd).name,
+                        new_dir
                     ))));
                 }
             }
             LineType::Input(Command::List) => {}
             LineType::Output(Content::Directory(name)) => {
-                let new_directory = Box::new(Directory::new_with_parent(&name, cwd));
-                cwd.sub_directories.push(new_directory);
+                fs.add_directory(cwd, &name);
             }
             LineType::Output(Content::File(name, size)) => {
-                cwd.files.push(File {
-                    _name: name,
-                    size: size,
-                });
+                fs.add_file(cwd, &name, size);
             }
         }
     }
-    Ok(root)
+    Ok(fs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k";
+
+    #[test]
+    fn solves_example() {
+        let ctx = Context::default();
+        assert_eq!(Day07 {}.part1(EXAMPLE, &ctx).unwrap(), "95437");
+        assert_eq!(Day07 {}.part2(EXAMPLE, &ctx).unwrap(), "24933642");
+    }
+
+    #[test]
+    fn parent_links_survive_growth() {
+        // Enough siblings to make the arena and the children maps reallocate
+        // while we are deep inside the tree.
+        let mut input = String::from("$ cd /\n$ ls\ndir top\n$ cd top\n$ ls\n");
+        for idx in 0..100 {
+            input += &format!("dir d{}\n", idx);
+        }
+        for idx in 0..100 {
+            input += &format!("$ cd d{}\n$ ls\n{} f\n$ cd ..\n", idx, idx + 1);
+        }
+        input += "$ cd ..\n$ ls\n7 root.txt\n";
+        let fs = build_filesystem(&input).unwrap();
+        assert_eq!(fs.nodes.len(), 102);
+        assert_eq!(fs.directory_sizes()[0], 5050 + 7);
+        for (idx, node) in fs.nodes.iter().enumerate().skip(2) {
+            assert_eq!(node.parent, Some(NodeId(1)), "node {}", idx);
+        }
+    }
+
+    #[test]
+    fn relisting_does_not_double_count() {
+        let fs = build_filesystem("$ cd /\n$ ls\ndir a\n5 x\n$ ls\ndir a\n5 x").unwrap();
+        assert_eq!(fs.nodes.len(), 2);
+        assert_eq!(fs.directory_sizes(), vec![5, 0]);
+    }
+
+    #[test]
+    fn rejects_bad_navigation() {
+        assert!(build_filesystem("$ cd /\n$ cd ..").is_err());
+        assert!(build_filesystem("$ cd /\n$ ls\ndir a\n$ cd b").is_err());
+    }
+
+    #[test]
+    fn resolves_paths() {
+        let fs = build_filesystem(EXAMPLE).unwrap();
+        let sizes = fs.directory_sizes();
+        let e = fs.resolve("/a/e").unwrap();
+        assert_eq!(e.size(&sizes), 584);
+        let Entry::Directory(id) = e else {
+            panic!("/a/e should be a directory");
+        };
+        assert_eq!(fs.path(id), "/a/e");
+        assert_eq!(fs.resolve("/a/e/../../d/k").unwrap().size(&sizes), 7214296);
+        assert_eq!(fs.resolve("/").unwrap(), Entry::Directory(Filesystem::ROOT));
+        assert_eq!(fs.resolve("/a/e/i/x"), None);
+        assert_eq!(fs.resolve("/.."), None);
+    }
+
+    #[test]
+    fn directory_sizes_include_subdirectories() {
+        let fs = build_filesystem(EXAMPLE).unwrap();
+        let paths: Vec<String> = (0..fs.nodes.len())
+            .map(|idx| fs.path(NodeId(idx)))
+            .collect();
+        assert_eq!(paths, ["/", "/a", "/d", "/a/e"]);
+        assert_eq!(fs.directory_sizes(), [48381165, 94853, 24933642, 584]);
+    }
+
+    #[test]
+    fn short_lines_are_errors() {
+        for input in ["$", "$ cd", "dir", "123", "$ cd a b", "abc def", "$ rm x"] {
+            let error = build_filesystem(input).unwrap_err().to_string();
+            assert!(error.starts_with("Invalid"), "{}: {}", input, error);
+        }
+    }
+
+    #[test]
+    fn walks_and_finds() {
+        let fs = build_filesystem(EXAMPLE).unwrap();
+        let sizes = fs.directory_sizes();
+        let paths: Vec<String> = fs
+            .walk(Filesystem::ROOT)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "/", "/b.txt", "/c.dat", "/a", "/a/f", "/a/g", "/a/h.lst", "/a/e", "/a/e/i", "/d",
+                "/d/d.ext", "/d/d.log", "/d/j", "/d/k"
+            ]
+        );
+        let found = run_query(&fs, &sizes, "find /d name=d.* size>6000000").unwrap();
+        assert_eq!(found, ["   8033020 file /d/d.log"]);
+        let du = run_query(&fs, &sizes, "du").unwrap();
+        assert_eq!(du.len(), 4);
+        assert!(run_query(&fs, &sizes, "find size~3").is_err());
+        assert!(run_query(&fs, &sizes, "du /b.txt").is_err());
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(glob_match("*.txt", "b.txt"));
+        assert!(glob_match("?", "i"));
+        assert!(glob_match("*a*b*", "xxaxxbxx"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("*.txt", "b.dat"));
+        assert!(!glob_match("a?", "a"));
+    }
+
+    #[test]
+    fn formats_human_sizes() {
+        assert_eq!(human_size(0), "0");
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(1025), "1.1K");
+        assert_eq!(human_size(10 * 1024), "10K");
+        assert_eq!(human_size(10 * 1024 - 1), "10K");
+        assert_eq!(human_size(48381165), "47M");
+    }
+
+    #[test]
+    fn renders_tree() {
+        let tree = tree_report(EXAMPLE, Some(2)).unwrap();
+        let lines: Vec<&str> = tree.lines().collect();
+        assert_eq!(lines[0], "/ (48381165)");
+        assert_eq!(lines[1], "├── a (94853)");
+        assert_eq!(lines[2], "│   ├── e (584)");
+        assert!(!tree.contains(" i "));
+        assert_eq!(lines.last().unwrap(), &"    └── k (7214296)");
+        assert_eq!(tree_report(EXAMPLE, None).unwrap().lines().count(), 14);
+    }
+
+    #[test]
+    fn ranks_directories() {
+        let du = du_report(EXAMPLE).unwrap();
+        let paths: Vec<&str> = du.lines().map(|line| line[6..].trim()).collect();
+        assert_eq!(paths, ["/", "/d", "/a", "/a/e"]);
+        let top = top_report(EXAMPLE, 2, DISK_SIZE).unwrap();
+        assert_eq!(top.lines().count(), 3);
+        assert!(top.lines().nth(1).unwrap().contains("69.1%"));
+        assert!(top_report(EXAMPLE, 2, 0).is_err());
+    }
 }
@@ -28,9 +28,9 @@ impl Grid {
             }
         }
         Grid {
-            width: width,
-            height: height,
-            trees: trees,
+            width,
+            height,
+            trees,
         }
     }
 
@@ -91,19 +91,14 @@ impl Grid {
         for y in 1..(self.height - 1) {
             for x in 1..(self.width - 1) {
                 let idx = self.index(x, y);
-                if self.trees[idx] > buffers.highest_left[idx]
+                visible[idx] = self.trees[idx] > buffers.highest_left[idx]
                     || self.trees[idx] > buffers.highest_right[idx]
                     || self.trees[idx] > buffers.highest_up[idx]
-                    || self.trees[idx] > buffers.highest_down[idx]
-                {
-                    visible[idx] = true;
-                } else {
-                    visible[idx] = false;
-                }
+                    || self.trees[idx] > buffers.highest_down[idx];
             }
         }
-        let num_visible = visible.into_iter().filter(|x| *x).count();
-        num_visible
+
+        visible.into_iter().filter(|x| *x).count()
     }
 
     fn scenic_score(&self, x: usize, y: usize) -> usize {
@@ -140,32 +135,26 @@ impl Grid {
                 break;
             }
         }
-        let scenic_score = left_viz * right_viz * up_viz * down_viz;
-        scenic_score
+
+        left_viz * right_viz * up_viz * down_viz
     }
 }
 
 impl Solution for Day08 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 08 - Part 1");
-        let input = std::fs::read_to_string("data/day08.txt")?;
-        let grid = Grid::new(&input);
+    fn part1(&self, input: &str, _ctx: &Context) -> Result<String> {
+        let grid = Grid::new(input);
         let buffers = grid.prepare_buffers();
         let num_visible = grid.compute_visibility(&buffers);
-        println!("Number of visible tress: {}", num_visible);
-        Ok(())
+        Ok(num_visible.to_string())
     }
 
-    fn part2(&self) -> Result<()> {
-        println!("Day 08 - Part 1");
-        let input = std::fs::read_to_string("data/day08.txt")?;
-        let grid = Grid::new(&input);
+    fn part2(&self, input: &str, _ctx: &Context) -> Result<String> {
+        let grid = Grid::new(input);
         let best_scenic_score = (0..grid.trees.len())
             .map(|idx| (idx % grid.width, idx / grid.width))
             .map(|(x, y)| grid.scenic_score(x, y))
             .max()
             .unwrap();
-        println!("Best scenic score: {}", best_scenic_score);
-        Ok(())
+        Ok(best_scenic_score.to_string())
     }
 }
@@ -57,21 +57,19 @@ fn update_long_positions(state: &mut LongState, direction: &Direction) -> LongSt
 }
 
 impl Solution for Day09 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 09 - Part 1");
-        let input = std::fs::read_to_string("data/day09.txt")?;
+    fn part1(&self, input: &str, ctx: &Context) -> Result<String> {
+        let num_knots = ctx.params.get("part1_knots", 1)?;
         let lines = input
             .lines()
-            .map(|line| parse_line(line))
+            .map(parse_line)
             .collect::<std::result::Result<Vec<(Direction, i32)>, SimpleError>>()?;
         let commands: Vec<Direction> = lines
             .into_iter()
-            .map(|(dir, count)| vec![dir; count as usize])
-            .flatten()
+            .flat_map(|(dir, count)| vec![dir; count as usize])
             .collect();
         let states: Vec<LongState> = commands
             .iter()
-            .scan(LongState::new(1), |state, dir| {
+            .scan(LongState::new(num_knots), |state, dir| {
                 Some(update_long_positions(state, dir))
             })
             .collect();
@@ -79,25 +77,22 @@ impl Solution for Day09 {
             .into_iter()
             .map(|state| *state.knots.last().unwrap())
             .collect();
-        println!("Number of unique tail positions: {}", tail_positions.len());
-        Ok(())
+        Ok(tail_positions.len().to_string())
     }
 
-    fn part2(&self) -> Result<()> {
-        println!("Day 09 - Part 2");
-        let input = std::fs::read_to_string("data/day09.txt")?;
+    fn part2(&self, input: &str, ctx: &Context) -> Result<String> {
+        let num_knots = ctx.params.get("part2_knots", 9)?;
         let lines = input
             .lines()
-            .map(|line| parse_line(line))
+            .map(parse_line)
             .collect::<std::result::Result<Vec<(Direction, i32)>, SimpleError>>()?;
         let commands: Vec<Direction> = lines
             .into_iter()
-            .map(|(dir, count)| vec![dir; count as usize])
-            .flatten()
+            .flat_map(|(dir, count)| vec![dir; count as usize])
             .collect();
         let states: Vec<LongState> = commands
             .iter()
-            .scan(LongState::new(9), |state, dir| {
+            .scan(LongState::new(num_knots), |state, dir| {
                 Some(update_long_positions(state, dir))
             })
             .collect();
@@ -105,8 +100,11 @@ impl Solution for Day09 {
             .into_iter()
             .map(|state| *state.knots.last().unwrap())
             .collect();
-        println!("Number of unique tail positions: {}", tail_positions.len());
-        Ok(())
+        Ok(tail_positions.len().to_string())
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["part1_knots", "part2_knots"]
     }
 }
 
@@ -15,11 +15,9 @@ fn parse_line(line: &str) -> std::result::Result<Instruction, SimpleError> {
         if parts[0] == "noop" {
             return Ok(Instruction::Noop);
         }
-    } else if parts.len() == 2 {
-        if parts[0] == "addx" {
-            if let Ok(value) = parts[1].parse::<i32>() {
-                return Ok(Instruction::AddX(value));
-            }
+    } else if parts.len() == 2 && parts[0] == "addx" {
+        if let Ok(value) = parts[1].parse::<i32>() {
+            return Ok(Instruction::AddX(value));
         }
     }
     Err(SimpleError::new(format!("Invalid line: '{}'", line)))
@@ -53,7 +51,7 @@ impl State {
     }
 }
 
-fn register_value_at_cycle(states: &Vec<State>, cycle_number: usize) -> Option<i32> {
+fn register_value_at_cycle(states: &[State], cycle_number: usize) -> Option<i32> {
     if states.is_empty() {
         return None;
     }
@@ -80,9 +78,7 @@ fn register_value_at_cycle(states: &Vec<State>, cycle_number: usize) -> Option<i
 }
 
 impl Solution for Day10 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 10 - Part 1");
-        let input = std::fs::read_to_string("data/day10.txt")?;
+    fn part1(&self, input: &str, _ctx: &Context) -> Result<String> {
         let instructions = input
             .lines()
             .map(parse_line)
@@ -98,22 +94,15 @@ impl Solution for Day10 {
             .collect();
         let mut cycle = 20;
         let mut cycle_values: Vec<(usize, i64)> = Vec::new();
-        loop {
-            if let Some(cycle_val) = register_value_at_cycle(&states, cycle - 1) {
-                cycle_values.push((cycle, cycle_val as i64));
-                cycle += 40;
-            } else {
-                break;
-            }
+        while let Some(cycle_val) = register_value_at_cycle(&states, cycle - 1) {
+            cycle_values.push((cycle, cycle_val as i64));
+            cycle += 40;
         }
         let signal_strength: i64 = cycle_values.iter().map(|(x, y)| (*x as i64) * (*y)).sum();
-        println!("Signal strength: {:#?}", signal_strength);
-        Ok(())
+        Ok(signal_strength.to_string())
     }
 
-    fn part2(&self) -> Result<()> {
-        println!("Day 10 - Part 2");
-        let input = std::fs::read_to_string("data/day10.txt")?;
+    fn part2(&self, input: &str, _ctx: &Context) -> Result<String> {
         let instructions = input
             .lines()
             .map(parse_line)
@@ -127,18 +116,19 @@ impl Solution for Day10 {
                 Some(retval)
             })
             .collect();
+        let mut screen = String::new();
         for cycle in 0..states.last().unwrap().cycle_counter {
             let xpos = (cycle) % 40;
             let register_value = register_value_at_cycle(&states, cycle).unwrap_or(1);
-            if ((xpos as i32) - register_value).abs() <= 1{
-                print!("#")
-            }else {
-                print!(".")
+            if ((xpos as i32) - register_value).abs() <= 1 {
+                screen.push('#')
+            } else {
+                screen.push('.')
             }
             if xpos == 39 {
-                println!();
+                screen.push('\n');
             }
         }
-        Ok(())
+        Ok(screen.trim_end().to_owned())
     }
 }
@@ -49,7 +49,48 @@ fn parse_operand(input: &str) -> Option<Operand> {
     }
 }
 
-fn parse_monkey(input: &str) -> Option<Monkey> {
+fn invalid(message: &str, line: &str) -> Box<dyn std::error::Error> {
+    Box::new(SimpleError::new(format!("{}: `{}`", message, line)))
+}
+
+fn missing(field: &str) -> Box<dyn std::error::Error> {
+    Box::new(SimpleError::new(format!(
+        "Monkey definition without {}",
+        field
+    )))
+}
+
+fn parse_operation(line: &str) -> Result<Operation> {
+    let payload = line
+        .split_once('=')
+        .ok_or_else(|| invalid("Invalid Operation definition", line))?
+        .1;
+    let (symbol, parts) = if payload.contains('+') {
+        ('+', payload.split('+').collect::<Vec<&str>>())
+    } else if payload.contains('*') {
+        ('*', payload.split('*').collect::<Vec<&str>>())
+    } else {
+        return Err(invalid("Invalid Operation definition", line));
+    };
+    if parts.len() != 2 {
+        return Err(invalid("Invalid operand count in", line));
+    }
+    let op0 = parse_operand(parts[0].trim()).ok_or_else(|| invalid("Invalid operand in", line))?;
+    let op1 = parse_operand(parts[1].trim()).ok_or_else(|| invalid("Invalid operand in", line))?;
+    Ok(match symbol {
+        '+' => Operation::Sum(op0, op1),
+        _ => Operation::Product(op0, op1),
+    })
+}
+
+fn parse_last<T: std::str::FromStr>(line: &str) -> Result<T> {
+    line.split_whitespace()
+        .last()
+        .and_then(|word| word.parse().ok())
+        .ok_or_else(|| invalid("Invalid number in", line))
+}
+
+fn parse_monkey(input: &str) -> Result<Monkey> {
     let lines = input.lines();
     let mut items: Option<Vec<i64>> = None;
     let mut operation: Option<Operation> = None;
@@ -58,65 +99,63 @@ fn parse_monkey(input: &str) -> Option<Monkey> {
     let mut false_target: Option<usize> = None;
 
     for line in lines.skip(1) {
-        let payload = line.split(":").last()?;
-        if line.contains("Starting items:") {
-            let parts: Vec<Option<i64>> = payload
-                .split(",")
+        if let Some((_, payload)) = line.split_once("Starting items:") {
+            let parts: Option<Vec<i64>> = payload
+                .split(',')
                 .map(|x| x.trim().parse::<i64>().ok())
                 .collect();
-            if parts.contains(&None) {
-                println!("Invalid line: `{}`", line);
-                return None;
-            }
-            items = Some(parts.into_iter().map(|x| x.unwrap()).collect::<Vec<i64>>());
+            items = Some(parts.ok_or_else(|| invalid("Invalid line", line))?);
         } else if line.contains("Operation:") {
-            let payload = line.split("=").last()?;
-            if payload.contains("+") {
-                let parts: Vec<&str> = payload.split("+").collect();
-                if parts.len() != 2 {
-                    println!("Invalid operand count in: `{}`", line);
-                    return None;
-                }
-                let op0 = parse_operand(parts[0].trim())?;
-                let op1 = parse_operand(parts[1].trim())?;
-                operation = Some(Operation::Sum(op0, op1));
-            } else if payload.contains("*") {
-                let parts: Vec<&str> = payload.split("*").collect();
-                if parts.len() != 2 {
-                    println!("Invalid operand count in: `{}`", line);
-                    return None;
-                }
-                let op0 = parse_operand(parts[0].trim())?;
-                let op1 = parse_operand(parts[1].trim())?;
-                operation = Some(Operation::Product(op0, op1));
-            } else {
-                println!("Invalid Operation definition: `{}`", line);
-                return None;
-            }
+            operation = Some(parse_operation(line)?);
         } else if line.contains("Test:") {
-            let parts = line.trim().split_whitespace();
-            div_test = parts.last()?.parse::<i64>().ok();
+            div_test = Some(parse_last(line)?);
         } else if line.contains("If true:") {
-            let parts = line.trim().split_whitespace();
-            true_target = parts.last()?.parse::<usize>().ok();
+            true_target = Some(parse_last(line)?);
         } else if line.contains("If false:") {
-            let parts = line.trim().split_whitespace();
-            false_target = parts.last()?.parse::<usize>().ok();
-        } else {
-            println!("Ignoring line: `{}`", line);
+            false_target = Some(parse_last(line)?);
+        } else if !line.trim().is_empty() {
+            return Err(invalid("Unexpected line", line));
         }
     }
-    Some(Monkey {
-        items: items?,
-        operation: operation?,
-        divisibility_test: div_test?,
-        true_target: true_target?,
-        false_target: false_target?,
+    Ok(Monkey {
+        items: items.ok_or_else(|| missing("starting items"))?,
+        operation: operation.ok_or_else(|| missing("an operation"))?,
+        divisibility_test: div_test.ok_or_else(|| missing("a test"))?,
+        true_target: true_target.ok_or_else(|| missing("an 'If true' target"))?,
+        false_target: false_target.ok_or_else(|| missing("an 'If false' target"))?,
     })
 }
 
+/// Every monkey in the input. Monkey business needs at least two of them, and
+/// every throw has to land on one of them.
+fn parse_monkeys(input: &str) -> Result<Vec<Monkey>> {
+    let monkeys: Vec<Monkey> = input
+        .split("\n\n")
+        .map(parse_monkey)
+        .collect::<Result<_>>()?;
+    if monkeys.len() < 2 {
+        return Err(Box::new(SimpleError::new(format!(
+            "Expected at least 2 monkeys, found {}",
+            monkeys.len()
+        ))));
+    }
+    for (idx, monkey) in monkeys.iter().enumerate() {
+        for target in [monkey.true_target, monkey.false_target] {
+            if target >= monkeys.len() {
+                return Err(Box::new(SimpleError::new(format!(
+                    "Monkey {} throws to monkey {}, but there are only {} monkeys",
+                    idx,
+                    target,
+                    monkeys.len()
+                ))));
+            }
+        }
+    }
+    Ok(monkeys)
+}
+
 fn run_monkey(monkeys: &mut [Monkey], idx: usize, divide: bool) -> usize {
-    let mut items: Vec<i64> = monkeys[idx].items.iter().rev().map(|x| *x).collect();
+    let mut items: Vec<i64> = monkeys[idx].items.iter().rev().copied().collect();
     let inspections = items.len();
     let modulus: i64 = monkeys.iter().map(|x| x.divisibility_test).product();
     while let Some(level) = items.pop() {
@@ -138,53 +177,122 @@ fn run_monkey(monkeys: &mut [Monkey], idx: usize, divide: bool) -> usize {
 }
 
 impl Solution for Day11 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 11 - Part 1");
-        let input = std::fs::read_to_string("data/day11.txt")?;
-        let definitions = input.split("\n\n");
-        let monkeys: Option<Vec<Monkey>> = definitions.map(parse_monkey).collect();
-        if let Some(mut monkeys) = monkeys {
-            let mut inspect_counters = vec![0; monkeys.len()];
-            for _ in 0..20 {
-                for i in 0..monkeys.len() {
-                    inspect_counters[i] += run_monkey(&mut monkeys, i, true);
-                }
+    fn part1(&self, input: &str, ctx: &Context) -> Result<String> {
+        let rounds = ctx.params.get("part1_rounds", 20)?;
+        let mut monkeys = parse_monkeys(input)?;
+        let mut inspect_counters = vec![0; monkeys.len()];
+        for _ in 0..rounds {
+            ctx.cancel.check()?;
+            for (i, counter) in inspect_counters.iter_mut().enumerate() {
+                *counter += run_monkey(&mut monkeys, i, true);
             }
-            println!("{:?}", inspect_counters);
-            inspect_counters.sort();
-            println!("{:?}", inspect_counters);
-            let monkey_business = inspect_counters[inspect_counters.len() - 1]
-                * inspect_counters[inspect_counters.len() - 2];
-
-            println!("Level of monkey business: {}", monkey_business);
-            Ok(())
-        } else {
-            Err(Box::new(SimpleError::new("Invalid monkeys")))
         }
+        inspect_counters.sort();
+        let monkey_business = inspect_counters[inspect_counters.len() - 1]
+            * inspect_counters[inspect_counters.len() - 2];
+        Ok(monkey_business.to_string())
     }
 
-    fn part2(&self) -> Result<()> {
-        println!("Day 11 - Part 2");
-        let input = std::fs::read_to_string("data/day11.txt")?;
-        let definitions = input.split("\n\n");
-        let monkeys: Option<Vec<Monkey>> = definitions.map(parse_monkey).collect();
-        if let Some(mut monkeys) = monkeys {
-            let mut inspect_counters = vec![0; monkeys.len()];
-            for _ in 0..10000 {
-                for i in 0..monkeys.len() {
-                    inspect_counters[i] += run_monkey(&mut monkeys, i, false);
-                }
+    fn part2(&self, input: &str, ctx: &Context) -> Result<String> {
+        let rounds = ctx.params.get("part2_rounds", 10000)?;
+        let mut monkeys = parse_monkeys(input)?;
+        let mut inspect_counters = vec![0; monkeys.len()];
+        for _ in 0..rounds {
+            ctx.cancel.check()?;
+            for (i, counter) in inspect_counters.iter_mut().enumerate() {
+                *counter += run_monkey(&mut monkeys, i, false);
             }
-            println!("{:?}", inspect_counters);
-            inspect_counters.sort();
-            println!("{:?}", inspect_counters);
-            let monkey_business = inspect_counters[inspect_counters.len() - 1]
-                * inspect_counters[inspect_counters.len() - 2];
-
-            println!("Level of monkey business: {}", monkey_business);
-            Ok(())
-        } else {
-            Err(Box::new(SimpleError::new("Invalid monkeys")))
         }
+        inspect_counters.sort();
+        let monkey_business = inspect_counters[inspect_counters.len() - 1]
+            * inspect_counters[inspect_counters.len() - 2];
+        Ok(monkey_business.to_string())
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["part1_rounds", "part2_rounds"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONKEY: &str = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 1
+    If false: throw to monkey 1";
+
+    fn error(definition: &str) -> String {
+        parse_monkey(definition).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_monkeys() {
+        let monkey = parse_monkey(MONKEY).unwrap();
+        assert_eq!(monkey.items, vec![79, 98]);
+        assert_eq!(evaluate(&monkey.operation, 2), 38);
+        assert_eq!(monkey.divisibility_test, 23);
+        assert_eq!((monkey.true_target, monkey.false_target), (1, 1));
+    }
+
+    #[test]
+    fn malformed_monkeys_are_errors() {
+        assert_eq!(
+            error(&MONKEY.replace("79, 98", "79, x")),
+            "Invalid line: `  Starting items: 79, x`"
+        );
+        assert_eq!(
+            error(&MONKEY.replace("old * 19", "old * 19 * 2")),
+            "Invalid operand count in: `  Operation: new = old * 19 * 2`"
+        );
+        assert_eq!(
+            error(&MONKEY.replace("old * 19", "old / 19")),
+            "Invalid Operation definition: `  Operation: new = old / 19`"
+        );
+        assert_eq!(
+            error(&MONKEY.replace("by 23", "by many")),
+            "Invalid number in: `  Test: divisible by many`"
+        );
+        assert_eq!(
+            error(&format!("{}\n  Mood: grumpy", MONKEY)),
+            "Unexpected line: `  Mood: grumpy`"
+        );
+        assert_eq!(
+            error(&MONKEY.replace("  Test: divisible by 23\n", "")),
+            "Monkey definition without a test"
+        );
+        let day = Day11 {};
+        assert!(day
+            .part1("Monkey 0:\n  Starting items: 1", &Context::default())
+            .is_err());
+    }
+
+    #[test]
+    fn needs_two_monkeys_with_valid_targets() {
+        let day = Day11 {};
+        let ctx = Context::default();
+        assert_eq!(
+            day.part1(MONKEY, &ctx).unwrap_err().to_string(),
+            "Expected at least 2 monkeys, found 1"
+        );
+        let second = MONKEY
+            .replace("Monkey 0", "Monkey 1")
+            .replace("old * 19", "old + 3")
+            .replace("monkey 1", "monkey 0");
+        let pair = format!("{}\n\n{}", MONKEY, second);
+        assert!(day.part1(&pair, &ctx).is_ok());
+        assert!(day.part2(&pair, &ctx).is_ok());
+        let stray = format!(
+            "{}\n\n{}",
+            MONKEY,
+            second.replace("false: throw to monkey 0", "false: throw to monkey 7")
+        );
+        assert_eq!(
+            day.part2(&stray, &ctx).unwrap_err().to_string(),
+            "Monkey 1 throws to monkey 7, but there are only 2 monkeys"
+        );
     }
 }
@@ -45,7 +45,7 @@ impl Terrain {
         Some(Terrain {
             rows: num_rows,
             cols: num_cols,
-            heightmap: heightmap,
+            heightmap,
             starting_idx: starting_idx?,
             target_idx: target_idx?,
         })
@@ -63,8 +63,9 @@ impl Terrain {
         self.heightmap[target_idx] <= (self.heightmap[pos_idx] + 1)
     }
 
-    pub fn shortest_path(&self, starting_index: usize) -> usize {
-        let mut steps_to: Vec<usize> = vec![std::usize::MAX; self.rows * self.cols];
+    /// The fewest steps from `starting_index` to the target, if it can be reached.
+    pub fn shortest_path(&self, starting_index: usize) -> Option<usize> {
+        let mut steps_to: Vec<usize> = vec![usize::MAX; self.rows * self.cols];
         let mut queue: VecDeque<((usize, usize), usize)> = VecDeque::new();
         queue.push_back((self.coordinates(starting_index), 0));
         while let Some(((cx, cy), current_steps)) = queue.pop_front() {
@@ -85,35 +86,59 @@ impl Terrain {
                 }
             }
         }
-        steps_to[self.target_idx]
+        Some(steps_to[self.target_idx]).filter(|steps| *steps != usize::MAX)
     }
 }
 
 impl Solution for Day12 {
-    fn part1(&self) -> Result<()> {
-        println!("Day 12 - Part 1");
-        let input = std::fs::read_to_string("data/day12.txt")?;
-        let terrain = Terrain::new(&input).unwrap();
-        let steps = terrain.shortest_path(terrain.starting_idx);
-        println!("Shortest path: {}", steps);
-        Ok(())
+    fn part1(&self, input: &str, _ctx: &Context) -> Result<String> {
+        let terrain = Terrain::new(input).ok_or_else(|| SimpleError::new("Invalid terrain"))?;
+        let steps = terrain
+            .shortest_path(terrain.starting_idx)
+            .ok_or_else(|| SimpleError::new("No path from the start to the target"))?;
+        Ok(steps.to_string())
     }
 
-    fn part2(&self) -> Result<()> {
-        println!("Day 12 - Part 2");
-        let input = std::fs::read_to_string("data/day12.txt")?;
-        let terrain = Terrain::new(&input).unwrap();
+    fn part2(&self, input: &str, ctx: &Context) -> Result<String> {
+        let terrain = Terrain::new(input).ok_or_else(|| SimpleError::new("Invalid terrain"))?;
         let potential_starts = terrain
             .heightmap
             .iter()
             .enumerate()
             .filter(|(_, height)| **height == 0)
             .map(|(idx, _)| idx);
-        let shortest_path = potential_starts
-            .map(|x| terrain.shortest_path(x))
-            .min()
-            .unwrap();
-        println!("Overall shortest path: {}", shortest_path);
-        Ok(())
+        let mut shortest_path: Option<usize> = None;
+        for start in potential_starts {
+            ctx.cancel.check()?;
+            if let Some(steps) = terrain.shortest_path(start) {
+                shortest_path = Some(shortest_path.map_or(steps, |best| best.min(steps)));
+            }
+        }
+        let steps = shortest_path
+            .ok_or_else(|| SimpleError::new("No path from any lowest square to the target"))?;
+        Ok(steps.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi";
+
+    #[test]
+    fn solves_example() {
+        let ctx = Context::default();
+        assert_eq!(Day12 {}.part1(EXAMPLE, &ctx).unwrap(), "31");
+        assert_eq!(Day12 {}.part2(EXAMPLE, &ctx).unwrap(), "29");
+    }
+
+    #[test]
+    fn unreachable_targets_are_errors() {
+        // A wall of `z` can only be climbed from `y`.
+        let walled = "Sazb\nbbzE";
+        let ctx = Context::default();
+        assert!(Day12 {}.part1(walled, &ctx).is_err());
+        assert!(Day12 {}.part2(walled, &ctx).is_err());
     }
 }
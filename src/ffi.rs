@@ -0,0 +1,104 @@
+//! C ABI for embedding the solvers. See `include/aoc.h` and `ffi/test_ffi.c`.
+
+use std::ffi::{c_char, CString};
+use std::sync::Arc;
+
+use crate::registry;
+use crate::runner::{self, RunOptions, Status, Task};
+
+pub const AOC_OK: i32 = 0;
+pub const AOC_INVALID_ARGUMENT: i32 = 1;
+pub const AOC_FAILED: i32 = 2;
+
+/// Number of available days. Valid days are `1..=aoc_day_count()`.
+#[no_mangle]
+pub extern "C" fn aoc_day_count() -> usize {
+    registry().len()
+}
+
+/// Solves `part` (1 or 2) of `day` for the `input_len` bytes at `input`.
+///
+/// Returns `AOC_OK` and stores the answer in `*out`, or another status code and
+/// stores an error message in `*out`. Either string must be released with
+/// `aoc_free_string`.
+///
+/// # Safety
+///
+/// `input` must point to `input_len` readable bytes (it may be null if
+/// `input_len` is 0) and `out` must be a valid pointer to writable storage.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_solve(
+    day: u32,
+    part: u32,
+    input: *const u8,
+    input_len: usize,
+    out: *mut *mut c_char,
+) -> i32 {
+    if out.is_null() {
+        return AOC_INVALID_ARGUMENT;
+    }
+    let (status, message) = solve(day as usize, part as usize, input, input_len);
+    *out = into_c_string(message);
+    status
+}
+
+/// Releases a string returned by `aoc_solve`. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `string` must be null or a pointer obtained from `aoc_solve` that has not
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+unsafe fn solve(day: usize, part: usize, input: *const u8, input_len: usize) -> (i32, String) {
+    let registry = registry();
+    if day < 1 || day > registry.len() {
+        return (
+            AOC_INVALID_ARGUMENT,
+            format!(
+                "Invalid day ({}), supported values: 1..{}",
+                day,
+                registry.len()
+            ),
+        );
+    }
+    if part != 1 && part != 2 {
+        return (
+            AOC_INVALID_ARGUMENT,
+            format!("Invalid part ({}), expected 1 or 2", part),
+        );
+    }
+    let bytes = if input_len == 0 {
+        &[][..]
+    } else if input.is_null() {
+        return (AOC_INVALID_ARGUMENT, "Input pointer is null".to_owned());
+    } else {
+        std::slice::from_raw_parts(input, input_len)
+    };
+    let input: Arc<str> = match std::str::from_utf8(bytes) {
+        Ok(input) => input.into(),
+        Err(_) => return (AOC_INVALID_ARGUMENT, "Input must be valid UTF-8".to_owned()),
+    };
+    let result = runner::run_task(
+        registry[day - 1].clone(),
+        day,
+        Task::Part(part),
+        input,
+        &RunOptions::default(),
+    );
+    match result.status {
+        Status::Solved(answer) => (AOC_OK, answer),
+        Status::Failed(error) => (AOC_FAILED, error),
+        Status::TimedOut(_) => (AOC_FAILED, "Timed out".to_owned()),
+    }
+}
+
+fn into_c_string(message: String) -> *mut c_char {
+    let message = message.replace('\0', "");
+    CString::new(message).unwrap_or_default().into_raw()
+}
@@ -0,0 +1,324 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use simple_error::SimpleError;
+
+use crate::solution::Result;
+
+/// A closed range of integers `start..=end`, never empty. `end` is below
+/// `i64::MAX` so that the position just past it always exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Interval {
+    pub fn new(start: i64, end: i64) -> Result<Self> {
+        if start > end {
+            return Err(Box::new(SimpleError::new(format!(
+                "Invalid range: {} > {}",
+                start, end
+            ))));
+        }
+        if end == i64::MAX {
+            return Err(Box::new(SimpleError::new(format!(
+                "Invalid range: {} is too large",
+                end
+            ))));
+        }
+        Ok(Self { start, end })
+    }
+
+    /// Number of integers in the interval, saturating at `i64::MAX`.
+    pub fn len(&self) -> i64 {
+        self.end.saturating_sub(self.start).saturating_add(1)
+    }
+
+    pub fn contains(&self, other: &Interval) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        (start <= end).then_some(Interval { start, end })
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The union as a single interval, if the two overlap or are adjacent.
+    pub fn union(&self, other: &Interval) -> Option<Interval> {
+        if self.start > other.end.saturating_add(1) || other.start > self.end.saturating_add(1) {
+            return None;
+        }
+        Some(Interval {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        })
+    }
+
+    /// The parts of `self` not in `other`: none, one, or two intervals.
+    pub fn difference(&self, other: &Interval) -> Vec<Interval> {
+        let Some(common) = self.intersection(other) else {
+            return vec![*self];
+        };
+        let mut parts = Vec::new();
+        if self.start < common.start {
+            parts.push(Interval {
+                start: self.start,
+                end: common.start - 1,
+            });
+        }
+        if common.end < self.end {
+            parts.push(Interval {
+                start: common.end + 1,
+                end: self.end,
+            });
+        }
+        parts
+    }
+}
+
+impl FromStr for Interval {
+    type Err = Box<dyn std::error::Error>;
+
+    /// Parses `start-end`.
+    fn from_str(input: &str) -> Result<Self> {
+        let parts: Vec<&str> = input.split('-').collect();
+        if parts.len() != 2 {
+            return Err(Box::new(SimpleError::new("Invalid range")));
+        }
+        Interval::new(parts[0].parse()?, parts[1].parse()?)
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// A union of intervals, kept sorted with overlapping and adjacent intervals
+/// merged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    /// Number of integers in the set, saturating at `i64::MAX`.
+    pub fn len(&self) -> i64 {
+        self.intervals
+            .iter()
+            .map(Interval::len)
+            .fold(0, i64::saturating_add)
+    }
+
+    /// The smallest interval containing the whole set.
+    pub fn span(&self) -> Option<Interval> {
+        Some(Interval {
+            start: self.intervals.first()?.start,
+            end: self.intervals.last()?.end,
+        })
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = Vec::new();
+        let mut first_cut = 0;
+        for interval in &self.intervals {
+            while other
+                .intervals
+                .get(first_cut)
+                .is_some_and(|cut| cut.end < interval.start)
+            {
+                first_cut += 1;
+            }
+            let mut rest = Some(*interval);
+            for cut in &other.intervals[first_cut..] {
+                let Some(piece) = rest else {
+                    break;
+                };
+                if cut.start > piece.end {
+                    break;
+                }
+                rest = None;
+                for part in piece.difference(cut) {
+                    if part.end < cut.start {
+                        result.push(part);
+                    } else {
+                        rest = Some(part);
+                    }
+                }
+            }
+            result.extend(rest);
+        }
+        IntervalSet { intervals: result }
+    }
+
+    /// Everything covered by at least `depth` of `intervals`.
+    pub fn covered_at_least(intervals: &[Interval], depth: usize) -> IntervalSet {
+        depth_profile(intervals)
+            .into_iter()
+            .filter(|(_, covered_by)| *covered_by >= depth)
+            .map(|(interval, _)| interval)
+            .collect()
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Interval>>(iter: T) -> Self {
+        let mut sorted: Vec<Interval> = iter.into_iter().collect();
+        sorted.sort_unstable();
+        let mut intervals: Vec<Interval> = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            match intervals.last_mut() {
+                Some(last) => match last.union(&interval) {
+                    Some(merged) => *last = merged,
+                    None => intervals.push(interval),
+                },
+                None => intervals.push(interval),
+            }
+        }
+        IntervalSet { intervals }
+    }
+}
+
+impl Display for IntervalSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self.intervals.iter().map(Interval::to_string).collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Start and end events of `intervals`, sorted so that at equal positions
+/// intervals ending just before it are removed before new ones are added.
+fn sweep_events(intervals: &[Interval]) -> Vec<(i64, i64)> {
+    let mut events: Vec<(i64, i64)> = Vec::with_capacity(2 * intervals.len());
+    for interval in intervals {
+        events.push((interval.start, 1));
+        events.push((interval.end + 1, -1));
+    }
+    events.sort_unstable();
+    events
+}
+
+/// The maximal pieces of the line covered by a constant, non-zero number of
+/// `intervals`, in order, each with that number.
+pub fn depth_profile(intervals: &[Interval]) -> Vec<(Interval, usize)> {
+    let events = sweep_events(intervals);
+    let mut profile: Vec<(Interval, usize)> = Vec::new();
+    let mut active = 0;
+    for (idx, (position, delta)) in events.iter().enumerate() {
+        active += delta;
+        let Some((next, _)) = events.get(idx + 1) else {
+            break;
+        };
+        if next == position || active == 0 {
+            continue;
+        }
+        let piece = Interval {
+            start: *position,
+            end: next - 1,
+        };
+        match profile.last_mut() {
+            Some((last, depth)) if *depth == active as usize && last.end + 1 == piece.start => {
+                last.end = piece.end
+            }
+            _ => profile.push((piece, active as usize)),
+        }
+    }
+    profile
+}
+
+/// Number of pairs among `intervals` that share at least one point.
+pub fn overlapping_pairs(intervals: &[Interval]) -> u64 {
+    let mut active = 0;
+    let mut pairs = 0;
+    for (_, delta) in sweep_events(intervals) {
+        if delta > 0 {
+            pairs += active;
+        }
+        active = (active as i64 + delta) as u64;
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iv(start: i64, end: i64) -> Interval {
+        Interval::new(start, end).unwrap()
+    }
+
+    fn set(intervals: &[(i64, i64)]) -> IntervalSet {
+        intervals
+            .iter()
+            .map(|(start, end)| iv(*start, *end))
+            .collect()
+    }
+
+    #[test]
+    fn union_of_disjoint_touching_nested_and_identical() {
+        assert_eq!(iv(1, 3).union(&iv(5, 7)), None);
+        assert_eq!(iv(5, 7).union(&iv(1, 3)), None);
+        assert_eq!(iv(1, 3).union(&iv(4, 7)), Some(iv(1, 7)));
+        assert_eq!(iv(4, 7).union(&iv(1, 3)), Some(iv(1, 7)));
+        assert_eq!(iv(1, 9).union(&iv(3, 4)), Some(iv(1, 9)));
+        assert_eq!(iv(3, 4).union(&iv(1, 9)), Some(iv(1, 9)));
+        assert_eq!(iv(2, 5).union(&iv(2, 5)), Some(iv(2, 5)));
+    }
+
+    #[test]
+    fn difference_of_disjoint_touching_nested_and_identical() {
+        assert_eq!(iv(1, 3).difference(&iv(5, 7)), vec![iv(1, 3)]);
+        assert_eq!(iv(1, 3).difference(&iv(4, 7)), vec![iv(1, 3)]);
+        assert_eq!(iv(1, 4).difference(&iv(4, 7)), vec![iv(1, 3)]);
+        assert_eq!(iv(4, 7).difference(&iv(1, 4)), vec![iv(5, 7)]);
+        assert_eq!(iv(1, 9).difference(&iv(3, 4)), vec![iv(1, 2), iv(5, 9)]);
+        assert_eq!(iv(3, 4).difference(&iv(1, 9)), vec![]);
+        assert_eq!(iv(2, 5).difference(&iv(2, 5)), vec![]);
+    }
+
+    #[test]
+    fn set_difference_of_disjoint_touching_nested_and_identical() {
+        let base = set(&[(1, 5), (10, 20)]);
+        assert_eq!(base.difference(&set(&[(6, 9), (21, 30)])), base);
+        assert_eq!(base.difference(&set(&[(5, 10)])), set(&[(1, 4), (11, 20)]));
+        assert_eq!(
+            base.difference(&set(&[(2, 3), (12, 12), (15, 16)])),
+            set(&[(1, 1), (4, 5), (10, 11), (13, 14), (17, 20)])
+        );
+        assert_eq!(base.difference(&set(&[(0, 25)])), IntervalSet::default());
+        assert_eq!(base.difference(&base), IntervalSet::default());
+    }
+
+    #[test]
+    fn sets_merge_touching_intervals() {
+        let merged = set(&[(4, 6), (1, 3), (8, 9)]);
+        assert_eq!(merged.to_string(), "1-6, 8-9");
+        assert_eq!(merged.len(), 8);
+        assert_eq!(merged.span(), Some(iv(1, 9)));
+    }
+
+    #[test]
+    fn rejects_bounds_without_a_successor() {
+        assert!(Interval::new(5, 4).is_err());
+        assert!(Interval::new(0, i64::MAX).is_err());
+        assert!("1-9223372036854775807".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn handles_bounds_near_the_limits() {
+        let wide = iv(i64::MIN, i64::MAX - 1);
+        assert_eq!(wide.len(), i64::MAX);
+        assert_eq!(set(&[(i64::MIN, -1), (1, i64::MAX - 1)]).len(), i64::MAX);
+        let top = iv(i64::MAX - 2, i64::MAX - 1);
+        assert_eq!(depth_profile(&[top, top]), vec![(top, 2)]);
+        assert_eq!(overlapping_pairs(&[top, top]), 1);
+        assert_eq!(wide.difference(&top), vec![iv(i64::MIN, i64::MAX - 3)]);
+    }
+}
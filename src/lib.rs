@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use solution::Solution;
+
+pub mod batch;
+pub mod config;
+pub mod ffi;
+pub mod output;
+pub mod runner;
+pub mod server;
+pub mod solution;
+pub mod util;
+
+mod day01;
+mod day02;
+mod day03;
+mod day04;
+mod day05;
+mod day06;
+mod day07;
+mod day08;
+mod day09;
+mod day10;
+mod day11;
+mod day12;
+mod interval;
+
+/// All solutions, indexed by `day - 1`.
+pub fn registry() -> Vec<Arc<dyn Solution>> {
+    vec![
+        Arc::new(day01::Day01 {}),
+        Arc::new(day02::Day02 {}),
+        Arc::new(day03::Day03 {}),
+        Arc::new(day04::Day04 {}),
+        Arc::new(day05::Day05 {}),
+        Arc::new(day06::Day06 {}),
+        Arc::new(day07::Day07 {}),
+        Arc::new(day08::Day08 {}),
+        Arc::new(day09::Day09 {}),
+        Arc::new(day10::Day10 {}),
+        Arc::new(day11::Day11 {}),
+        Arc::new(day12::Day12 {}),
+    ]
+}
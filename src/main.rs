@@ -1,59 +1,218 @@
-use clap::Parser;
-use solution::Solution;
-
-mod solution;
-mod util;
-
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use advent_of_code_2022::config::{self, Config};
+use advent_of_code_2022::output::{self, OutputFormat};
+use advent_of_code_2022::runner::{self, RunOptions, Task};
+use advent_of_code_2022::solution::{Params, Solution};
+use advent_of_code_2022::{batch, registry, server, util};
+use clap::{Parser, Subcommand};
 
 #[derive(Parser, Debug)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     #[arg(long)]
     day: Option<usize>,
+    /// Maximum time allowed per part, e.g. `500ms`, `10s` or `2m`.
+    #[arg(long, global = true, value_parser = util::parse_duration)]
+    timeout: Option<Duration>,
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+    /// Run the selected days against every `dayNN*.txt` file in this directory.
+    #[arg(long)]
+    inputs: Option<PathBuf>,
+    /// Directory holding the `dayNN.txt` puzzle inputs [default: data]
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+    /// Number of days to run in parallel [default: 1]
+    #[arg(long)]
+    jobs: Option<usize>,
+    /// Override a solution parameter for the selected days (all of them when
+    /// serving), e.g. `--param top_n=5`.
+    #[arg(long = "param", global = true, value_parser = util::parse_key_value)]
+    params: Vec<(String, String)>,
+    /// Run one of a day's extra modes instead of the two parts, e.g. `--mode report`.
+    #[arg(long)]
+    mode: Option<String>,
+    /// Configuration file [default: aoc.toml if present]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Serve the solvers over HTTP on localhost.
+    Serve {
+        #[arg(long, default_value_t = 8022)]
+        port: u16,
+    },
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2);
+}
+
+/// Adds the `--param` overrides to the parameters of each of `days`, failing on keys
+/// that none of those days reads.
+fn apply_overrides(
+    params: &mut HashMap<usize, Params>,
+    registry: &[Arc<dyn Solution>],
+    days: &[usize],
+    overrides: &[(String, String)],
+) {
+    let mut known: Vec<&str> = Vec::new();
+    for day in days {
+        for key in registry[day - 1].params() {
+            if !known.contains(key) {
+                known.push(key);
+            }
+        }
+    }
+    for (key, _) in overrides {
+        if !known.contains(&key.as_str()) {
+            let context = match days {
+                [day] => format!("--param for day {}", day),
+                _ => "--param for the selected days".to_owned(),
+            };
+            fail(&config::unknown_param(key, &context, &known));
+        }
+    }
+    for day in days {
+        let day_params = params.entry(*day).or_default();
+        for (key, value) in overrides {
+            day_params.insert(key, value);
+        }
+    }
 }
 
 fn main() {
     let args = Args::parse();
-    let registry: Vec<Box<dyn Solution>> = vec![
-        Box::new(day01::Day01 {}),
-        Box::new(day02::Day02 {}),
-        Box::new(day03::Day03 {}),
-        Box::new(day04::Day04 {}),
-        Box::new(day05::Day05 {}),
-        Box::new(day06::Day06 {}),
-        Box::new(day07::Day07 {}),
-        Box::new(day08::Day08 {}),
-        Box::new(day09::Day09 {}),
-        Box::new(day10::Day10 {}),
-        Box::new(day11::Day11 {}),
-        Box::new(day12::Day12 {}),
-    ];
-
-    if let Some(selected_day) = args.day {
-        if selected_day < 1 || selected_day > registry.len() {
-            panic!(
+    let registry = registry();
+    let config = Config::load(args.config.as_deref()).unwrap_or_else(|e| fail(&e.to_string()));
+
+    let timeout = match (args.timeout, &config.timeout) {
+        (Some(timeout), _) => Some(timeout),
+        (None, Some(timeout)) => Some(util::parse_duration(timeout).unwrap_or_else(|e| fail(&e))),
+        (None, None) => None,
+    };
+    let mut params = config
+        .day_params(&registry)
+        .unwrap_or_else(|e| fail(&e.to_string()));
+
+    if let Some(Command::Serve { port }) = args.command {
+        let all_days: Vec<usize> = (1..=registry.len()).collect();
+        apply_overrides(&mut params, &registry, &all_days, &args.params);
+        let options = RunOptions { timeout, params };
+        if let Err(error) = server::serve(port, registry, options) {
+            eprintln!("Server error: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let format = args.format.or(config.format).unwrap_or(OutputFormat::Text);
+    let jobs = args.jobs.or(config.jobs).unwrap_or(1);
+    let data_dir = args
+        .data_dir
+        .or(config.data_dir)
+        .unwrap_or_else(|| PathBuf::from("data"));
+    let mut days: Vec<usize> = match (args.day, config.days) {
+        (Some(day), _) => vec![day],
+        (None, Some(days)) => days,
+        (None, None) => (1..=registry.len()).collect(),
+    };
+    for &day in &days {
+        if day < 1 || day > registry.len() {
+            fail(&format!(
                 "Invalid day ({}), supported values: 1..{}",
-                selected_day,
+                day,
                 registry.len()
-            );
+            ));
+        }
+    }
+    apply_overrides(&mut params, &registry, &days, &args.params);
+    let options = RunOptions { timeout, params };
+    let all_days_selected = days.len() == registry.len();
+    let tasks = match args.mode {
+        Some(mode) => {
+            if args.day.is_none() {
+                let supported: Vec<String> = days
+                    .iter()
+                    .filter(|&&day| !registry[day - 1].modes().is_empty())
+                    .map(|&day| format!("day {}: {}", day, registry[day - 1].modes().join(", ")))
+                    .collect();
+                days.retain(|&day| registry[day - 1].modes().contains(&mode.as_str()));
+                if days.is_empty() {
+                    fail(&format!(
+                        "No selected day supports mode '{}', available modes: {}",
+                        mode,
+                        if supported.is_empty() {
+                            "none".to_owned()
+                        } else {
+                            supported.join("; ")
+                        }
+                    ));
+                }
+            }
+            vec![Task::Mode(mode)]
         }
-        registry[selected_day - 1].part1().unwrap();
-        registry[selected_day - 1].part2().unwrap();
-    } else {
-        for day in registry {
-            day.part1().unwrap();
-            day.part2().unwrap();
+        None => Task::parts(),
+    };
+
+    if let Some(dir) = &args.inputs {
+        let mut all_ok = true;
+        let mut found_any = false;
+        runner::for_each_ordered(
+            &days,
+            jobs,
+            |&day| {
+                (
+                    day,
+                    batch::run_batch(&registry[day - 1], day, dir, &tasks, &options),
+                )
+            },
+            |(day, entries)| match entries {
+                Ok(entries) if entries.is_empty() && all_days_selected => {}
+                Ok(entries) => {
+                    found_any |= !entries.is_empty();
+                    all_ok &= entries.iter().all(|entry| entry.is_ok());
+                    let rendered = batch::render(format, day, &entries);
+                    if !rendered.is_empty() {
+                        println!("{}", rendered);
+                    }
+                }
+                Err(error) => fail(&format!("Failed to read {}: {}", dir.display(), error)),
+            },
+        );
+        if !found_any {
+            fail(&format!("No inputs found in {}", dir.display()));
         }
+        if !all_ok {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut all_solved = true;
+    runner::for_each_ordered(
+        &days,
+        jobs,
+        |&day| {
+            let path = data_dir.join(format!("day{:02}.txt", day));
+            runner::run_file(&registry[day - 1], day, &path, &tasks, &options)
+        },
+        |results| {
+            for result in results {
+                all_solved &= result.is_solved();
+                println!("{}", output::render(format, &result));
+            }
+        },
+    );
+    if !all_solved {
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,58 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::runner::{Status, Task, TaskResult};
+use crate::util::json_string;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+pub fn render(format: OutputFormat, result: &TaskResult) -> String {
+    match format {
+        OutputFormat::Text => render_text(result),
+        OutputFormat::Json => render_json(result),
+    }
+}
+
+fn render_text(result: &TaskResult) -> String {
+    let header = format!("Day {:02} - {}", result.day, result.task);
+    match &result.status {
+        Status::Solved(answer) => format!("{}\n{}", header, answer),
+        Status::Failed(error) => format!("{}\nError: {}", header, error),
+        Status::TimedOut(timeout) => format!("{}\nTimed out after {:?}", header, timeout),
+    }
+}
+
+/// One JSON object per line, so results can be streamed as days finish.
+fn render_json(result: &TaskResult) -> String {
+    format!("{{{}}}", json_fields(result))
+}
+
+/// The members of a result's JSON object, without the surrounding braces.
+pub fn json_fields(result: &TaskResult) -> String {
+    let status = match &result.status {
+        Status::Solved(answer) => {
+            format!("\"status\":\"solved\",\"answer\":{}", json_string(answer))
+        }
+        Status::Failed(error) => format!("\"status\":\"failed\",\"error\":{}", json_string(error)),
+        Status::TimedOut(timeout) => format!(
+            "\"status\":\"timed_out\",\"timeout_ms\":{}",
+            timeout.as_millis()
+        ),
+    };
+    let task = match &result.task {
+        Task::Part(part) => format!("\"part\":{}", part),
+        Task::Mode(mode) => format!("\"mode\":{}", json_string(mode)),
+    };
+    format!(
+        "\"day\":{},{},{},\"elapsed_ms\":{:.3}",
+        result.day,
+        task,
+        status,
+        result.elapsed.as_secs_f64() * 1000.0
+    )
+}
@@ -0,0 +1,298 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::solution::*;
+use simple_error::SimpleError;
+
+#[derive(Debug)]
+pub enum Status {
+    Solved(String),
+    Failed(String),
+    TimedOut(Duration),
+}
+
+/// What to run for a day: one of the two puzzle parts or one of its extra modes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Task {
+    Part(usize),
+    Mode(String),
+}
+
+impl Task {
+    pub fn parts() -> Vec<Task> {
+        vec![Task::Part(1), Task::Part(2)]
+    }
+
+    /// Short identifier, `part1`, `part2` or the mode name.
+    pub fn name(&self) -> String {
+        match self {
+            Task::Part(part) => format!("part{}", part),
+            Task::Mode(mode) => mode.clone(),
+        }
+    }
+}
+
+impl Display for Task {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Task::Part(part) => write!(f, "Part {}", part),
+            Task::Mode(mode) => write!(f, "{}", mode),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TaskResult {
+    pub day: usize,
+    pub task: Task,
+    pub status: Status,
+    pub elapsed: Duration,
+}
+
+impl TaskResult {
+    pub fn failed(day: usize, task: Task, error: String) -> Self {
+        Self {
+            day,
+            task,
+            status: Status::Failed(error),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        matches!(self.status, Status::Solved(_))
+    }
+}
+
+/// Settings shared by every task that gets run.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub timeout: Option<Duration>,
+    pub params: HashMap<usize, Params>,
+}
+
+impl RunOptions {
+    pub fn params(&self, day: usize) -> Params {
+        self.params.get(&day).cloned().unwrap_or_default()
+    }
+}
+
+/// Runs one task of a solution on a worker thread. If `timeout` elapses first the
+/// solution's cancellation token is triggered and the worker is left to wind down
+/// on its own, so a stuck day never blocks the ones after it.
+pub fn run_task(
+    solution: Arc<dyn Solution>,
+    day: usize,
+    task: Task,
+    input: Arc<str>,
+    options: &RunOptions,
+) -> TaskResult {
+    let ctx = Context {
+        cancel: CancellationToken::default(),
+        params: options.params(day),
+    };
+    let timeout = options.timeout;
+    let worker_ctx = ctx.clone();
+    let (sender, receiver) = mpsc::channel();
+    let worker_task = task.clone();
+    let start = Instant::now();
+    thread::spawn(move || {
+        let result = match worker_task {
+            Task::Part(1) => solution.part1(&input, &worker_ctx),
+            Task::Part(2) => solution.part2(&input, &worker_ctx),
+            Task::Part(part) => Err(Box::new(SimpleError::new(format!(
+                "Invalid part ({}), expected 1 or 2",
+                part
+            ))) as Box<dyn std::error::Error>),
+            Task::Mode(mode) => solution.run_mode(&mode, &input, &worker_ctx),
+        };
+        let _ = sender.send(result.map_err(|error| error.to_string()));
+    });
+
+    let received = match timeout {
+        Some(timeout) => receiver.recv_timeout(timeout),
+        None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    };
+    let status = match received {
+        Ok(Ok(answer)) => Status::Solved(answer),
+        Ok(Err(error)) => Status::Failed(error),
+        Err(RecvTimeoutError::Timeout) => {
+            ctx.cancel.cancel();
+            Status::TimedOut(timeout.unwrap_or_default())
+        }
+        Err(RecvTimeoutError::Disconnected) => Status::Failed("Solution panicked".to_owned()),
+    };
+    TaskResult {
+        day,
+        task,
+        status,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Runs `tasks` of a day, in order, on the contents of `path`.
+pub fn run_file(
+    solution: &Arc<dyn Solution>,
+    day: usize,
+    path: &Path,
+    tasks: &[Task],
+    options: &RunOptions,
+) -> Vec<TaskResult> {
+    match std::fs::read_to_string(path) {
+        Ok(input) => {
+            let input: Arc<str> = input.into();
+            tasks
+                .iter()
+                .map(|task| run_task(solution.clone(), day, task.clone(), input.clone(), options))
+                .collect()
+        }
+        Err(error) => tasks
+            .iter()
+            .map(|task| {
+                TaskResult::failed(day, task.clone(), format!("{}: {}", path.display(), error))
+            })
+            .collect(),
+    }
+}
+
+/// Calls `f` on every item using up to `jobs` threads and passes the results to
+/// `emit` in the original order, each as soon as all earlier ones are done.
+pub fn for_each_ordered<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    f: impl Fn(&T) -> R + Sync,
+    mut emit: impl FnMut(R),
+) {
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        let (next, f) = (&next, &f);
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            let sender = sender.clone();
+            scope.spawn(move || loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                if idx >= items.len() || sender.send((idx, f(&items[idx]))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_to_emit = 0;
+        for (idx, result) in receiver {
+            pending.insert(idx, result);
+            while let Some(result) = pending.remove(&next_to_emit) {
+                emit(result);
+                next_to_emit += 1;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_each_ordered_keeps_the_input_order() {
+        // Earlier items take longest, so they finish last.
+        let items: Vec<u64> = (0..8).collect();
+        let mut emitted = Vec::new();
+        for_each_ordered(
+            &items,
+            4,
+            |item| {
+                thread::sleep(Duration::from_millis(5 * (8 - item)));
+                item * 10
+            },
+            |result| emitted.push(result),
+        );
+        assert_eq!(emitted, vec![0, 10, 20, 30, 40, 50, 60, 70]);
+    }
+
+    #[test]
+    fn for_each_ordered_handles_more_jobs_than_items() {
+        let mut emitted = Vec::new();
+        for_each_ordered(&[1, 2], 16, |item| item + 1, |result| emitted.push(result));
+        assert_eq!(emitted, vec![2, 3]);
+        for_each_ordered(&[] as &[i32], 4, |item| *item, |_| panic!("no items"));
+    }
+
+    /// Part 1 spins until cancelled, part 2 answers straight away.
+    struct Stuck;
+
+    impl Solution for Stuck {
+        fn part1(&self, _input: &str, ctx: &Context) -> Result<String> {
+            loop {
+                ctx.cancel.check()?;
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        fn part2(&self, input: &str, _ctx: &Context) -> Result<String> {
+            Ok(input.len().to_string())
+        }
+    }
+
+    fn timeout_options(timeout: Duration) -> RunOptions {
+        RunOptions {
+            timeout: Some(timeout),
+            ..RunOptions::default()
+        }
+    }
+
+    #[test]
+    fn stuck_tasks_time_out() {
+        let timeout = Duration::from_millis(50);
+        let result = run_task(
+            Arc::new(Stuck),
+            1,
+            Task::Part(1),
+            "input".into(),
+            &timeout_options(timeout),
+        );
+        assert!(matches!(result.status, Status::TimedOut(after) if after == timeout));
+        assert!(!result.is_solved());
+        assert!(result.elapsed >= timeout);
+    }
+
+    #[test]
+    fn runs_continue_after_a_timeout() {
+        let registry: Vec<Arc<dyn Solution>> = vec![Arc::new(Stuck), Arc::new(Stuck)];
+        let options = timeout_options(Duration::from_millis(50));
+        let mut statuses = Vec::new();
+        for_each_ordered(
+            &[1, 2],
+            1,
+            |&day| {
+                let solution = registry[day - 1].clone();
+                (1..=2)
+                    .map(|part| {
+                        run_task(
+                            solution.clone(),
+                            day,
+                            Task::Part(part),
+                            "abc".into(),
+                            &options,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            },
+            |results| {
+                statuses.extend(results.into_iter().map(|result| match result.status {
+                    Status::Solved(answer) => answer,
+                    Status::Failed(error) => error,
+                    Status::TimedOut(_) => "timed out".to_owned(),
+                }))
+            },
+        );
+        assert_eq!(statuses, ["timed out", "3", "timed out", "3"]);
+    }
+}
@@ -0,0 +1,209 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use crate::output::{self, OutputFormat};
+use crate::runner::{self, RunOptions, Status, Task};
+use crate::solution::Solution;
+use crate::util::json_string;
+
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: String) -> Self {
+        Self { status, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, format!("{{\"error\":{}}}", json_string(message)))
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        504 => "Gateway Timeout",
+        _ => "Unknown",
+    }
+}
+
+/// Serves the registry on localhost until the process is stopped.
+///
+/// * `GET /days` lists the available days.
+/// * `POST /solve/<day>/<part>` runs a part, or one of the day's extra modes,
+///   on the request body.
+pub fn serve(port: u16, registry: Vec<Arc<dyn Solution>>, options: RunOptions) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    io::stdout().flush()?;
+    let registry = Arc::new(registry);
+    let options = Arc::new(options);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("Failed to accept connection: {}", error);
+                continue;
+            }
+        };
+        let registry = registry.clone();
+        let options = options.clone();
+        thread::spawn(move || {
+            if let Err(error) = handle_connection(stream, &registry, &options) {
+                eprintln!("Connection error: {}", error);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    registry: &[Arc<dyn Solution>],
+    options: &RunOptions,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = match read_request(&mut reader) {
+        Ok(request) => route(&request, registry, options),
+        Err(response) => response,
+    };
+    write_response(stream, &response)
+}
+
+fn read_request(reader: &mut impl BufRead) -> std::result::Result<Request, Response> {
+    let bad_request = |message: &str| Response::error(400, message);
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .map_err(|_| bad_request("Unreadable request line"))?;
+    let parts: Vec<&str> = request_line.split_whitespace().collect();
+    if parts.len() != 3 {
+        return Err(bad_request("Malformed request line"));
+    }
+    let (method, path) = (parts[0].to_owned(), parts[1].to_owned());
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader
+            .read_line(&mut header)
+            .map_err(|_| bad_request("Unreadable header"))?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| bad_request("Invalid Content-Length"))?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        return Err(Response::error(413, "Request body too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| bad_request("Request body shorter than Content-Length"))?;
+    Ok(Request { method, path, body })
+}
+
+fn route(request: &Request, registry: &[Arc<dyn Solution>], options: &RunOptions) -> Response {
+    let path = request.path.split('?').next().unwrap_or("");
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["days"] => {
+            if request.method != "GET" {
+                return Response::error(405, "Use GET for /days");
+            }
+            let days: Vec<String> = (1..=registry.len()).map(|day| day.to_string()).collect();
+            Response::json(200, format!("{{\"days\":[{}]}}", days.join(",")))
+        }
+        ["solve", day, part] => {
+            if request.method != "POST" {
+                return Response::error(405, "Use POST for /solve/<day>/<part>");
+            }
+            solve(day, part, &request.body, registry, options)
+        }
+        _ => Response::error(404, &format!("No such endpoint: {}", path)),
+    }
+}
+
+fn solve(
+    day: &str,
+    part: &str,
+    body: &[u8],
+    registry: &[Arc<dyn Solution>],
+    options: &RunOptions,
+) -> Response {
+    let day = match day.parse::<usize>() {
+        Ok(day) if day >= 1 && day <= registry.len() => day,
+        _ => {
+            return Response::error(
+                400,
+                &format!(
+                    "Invalid day ({}), supported values: 1..{}",
+                    day,
+                    registry.len()
+                ),
+            )
+        }
+    };
+    let solution = &registry[day - 1];
+    let task = match part.parse::<usize>() {
+        Ok(part) if part == 1 || part == 2 => Task::Part(part),
+        _ if solution.modes().contains(&part) => Task::Mode(part.to_owned()),
+        _ => {
+            return Response::error(
+                400,
+                &format!(
+                    "Invalid part ({}), expected 1, 2 or a mode of day {}",
+                    part, day
+                ),
+            )
+        }
+    };
+    let input = match std::str::from_utf8(body) {
+        Ok(input) => input,
+        Err(_) => return Response::error(400, "Input must be valid UTF-8"),
+    };
+    let result = runner::run_task(solution.clone(), day, task, input.into(), options);
+    let status = match result.status {
+        Status::Solved(_) => 200,
+        Status::Failed(_) => 422,
+        Status::TimedOut(_) => 504,
+    };
+    Response::json(status, output::render(OutputFormat::Json, &result))
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
@@ -1,6 +1,114 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use simple_error::SimpleError;
+
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-pub trait Solution {
-    fn part1(&self) -> Result<()>;
-    fn part2(&self) -> Result<()>;
-}
\ No newline at end of file
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Shared flag that long-running solutions poll so a watchdog can stop them.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns `Err(Cancelled)` once the token has been cancelled.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(Box::new(Cancelled))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Per-day overrides for values the solutions would otherwise hard-code.
+#[derive(Debug, Clone, Default)]
+pub struct Params {
+    values: HashMap<String, String>,
+}
+
+impl Params {
+    pub fn insert(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_owned(), value.to_owned());
+    }
+
+    /// The parsed value of `key`, or `default` if it isn't set.
+    pub fn get<T: FromStr>(&self, key: &str, default: T) -> Result<T> {
+        Ok(self.get_opt(key)?.unwrap_or(default))
+    }
+
+    /// The parsed value of `key`, or `None` if it isn't set.
+    pub fn get_opt<T: FromStr>(&self, key: &str) -> Result<Option<T>> {
+        match self.values.get(key) {
+            Some(value) => match value.parse::<T>() {
+                Ok(parsed) => Ok(Some(parsed)),
+                Err(_) => Err(Box::new(SimpleError::new(format!(
+                    "Invalid value for parameter '{}': '{}'",
+                    key, value
+                )))),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    pub cancel: CancellationToken,
+    pub params: Params,
+}
+
+pub trait Solution: Send + Sync {
+    fn part1(&self, input: &str, ctx: &Context) -> Result<String>;
+    fn part2(&self, input: &str, ctx: &Context) -> Result<String>;
+
+    /// Names of the extra modes this day supports besides the two parts.
+    fn modes(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Names of the parameters this day reads from `Context::params`.
+    fn params(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn run_mode(&self, mode: &str, _input: &str, _ctx: &Context) -> Result<String> {
+        Err(unsupported_mode(mode, self.modes()))
+    }
+}
+
+pub fn unsupported_mode(mode: &str, supported: &[&str]) -> Box<dyn Error> {
+    Box::new(SimpleError::new(if supported.is_empty() {
+        format!("Unsupported mode '{}', this day has no extra modes", mode)
+    } else {
+        format!(
+            "Unsupported mode '{}', supported modes: {}",
+            mode,
+            supported.join(", ")
+        )
+    }))
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+/// Parses durations such as `500ms`, `10s`, `2m` or a bare number of seconds.
+pub fn parse_duration(input: &str) -> std::result::Result<Duration, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("Invalid duration: '{}'", input))?;
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return Err(format!("Invalid duration unit in '{}'", input)),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("Duration out of range: '{}'", input))
+}
+
+/// Parses a `key=value` pair.
+pub fn parse_key_value(input: &str) -> std::result::Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_owned(), value.trim().to_owned()))
+        }
+        _ => Err(format!("Expected key=value, got '{}'", input)),
+    }
+}
+
+/// Quotes and escapes a string for embedding in JSON output.
+pub fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_value_pairs() {
+        assert_eq!(
+            parse_key_value("top_n=5"),
+            Ok(("top_n".to_owned(), "5".to_owned()))
+        );
+        assert_eq!(
+            parse_key_value(" key = a=b "),
+            Ok(("key".to_owned(), "a=b".to_owned()))
+        );
+        assert_eq!(
+            parse_key_value("key="),
+            Ok(("key".to_owned(), String::new()))
+        );
+        assert!(parse_key_value("key").is_err());
+        assert!(parse_key_value("=value").is_err());
+        assert!(parse_key_value(" =value").is_err());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("10s"), Ok(Duration::from_secs(10)));
+        assert_eq!(parse_duration(" 2 "), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("0"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for input in ["", "s", "ten", "1.2.3s", "5d", "-1s", "1e3s", "inf", "NaN"] {
+            assert!(parse_duration(input).is_err(), "{}", input);
+        }
+        let huge = format!("{}h", "9".repeat(30));
+        assert_eq!(
+            parse_duration(&huge),
+            Err(format!("Duration out of range: '{}'", huge))
+        );
+    }
+
+    #[test]
+    fn escapes_json_strings() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string(""), "\"\"");
+        assert_eq!(json_string("say \"hi\"\\ok"), "\"say \\\"hi\\\"\\\\ok\"");
+        assert_eq!(json_string("a\nb\r\tc"), "\"a\\nb\\r\\tc\"");
+        assert_eq!(json_string("\u{1}\u{1f}"), "\"\\u0001\\u001f\"");
+        assert_eq!(json_string("µ ✓"), "\"µ ✓\"");
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+const DAY01_EXAMPLE: &str = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+
+struct Server {
+    child: Child,
+    port: u16,
+}
+
+impl Server {
+    fn start(extra_args: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_advent_of_code_2022"))
+            .args(extra_args)
+            .args(["serve", "--port", "0"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to start server");
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let port = line
+            .trim()
+            .rsplit(':')
+            .next()
+            .and_then(|port| port.parse().ok())
+            .unwrap_or_else(|| panic!("unexpected banner: {}", line));
+        Server { child, port }
+    }
+
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap_or("").to_owned();
+        (status, body)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn solves_both_parts() {
+    let server = Server::start(&[]);
+    let (status, body) = server.request("POST", "/solve/1/1", DAY01_EXAMPLE);
+    assert_eq!(status, 200);
+    assert!(body.contains("\"status\":\"solved\""), "{}", body);
+    assert!(body.contains("\"answer\":\"24000\""), "{}", body);
+    assert!(body.contains("\"elapsed_ms\":"), "{}", body);
+
+    let (status, body) = server.request("POST", "/solve/1/2", DAY01_EXAMPLE);
+    assert_eq!(status, 200);
+    assert!(body.contains("\"answer\":\"45000\""), "{}", body);
+}
+
+#[test]
+fn runs_extra_modes() {
+    let server = Server::start(&[]);
+    let (status, body) = server.request("POST", "/solve/1/report", DAY01_EXAMPLE);
+    assert_eq!(status, 200);
+    assert!(body.contains("\"mode\":\"report\""), "{}", body);
+    assert!(body.contains("Elves: 5"), "{}", body);
+
+    assert_eq!(server.request("POST", "/solve/2/report", "").0, 400);
+}
+
+#[test]
+fn lists_days() {
+    let server = Server::start(&[]);
+    let (status, body) = server.request("GET", "/days", "");
+    assert_eq!(status, 200);
+    assert!(body.starts_with("{\"days\":[1,2,3,"), "{}", body);
+}
+
+#[test]
+fn rejects_invalid_requests() {
+    let server = Server::start(&[]);
+    assert_eq!(server.request("POST", "/solve/0/1", "").0, 400);
+    assert_eq!(server.request("POST", "/solve/99/1", "").0, 400);
+    assert_eq!(server.request("POST", "/solve/1/3", "").0, 400);
+    assert_eq!(server.request("GET", "/solve/1/1", "").0, 405);
+    assert_eq!(server.request("GET", "/nowhere", "").0, 404);
+
+    let (status, body) = server.request("POST", "/solve/1/1", "not a number");
+    assert_eq!(status, 422);
+    assert!(body.contains("\"status\":\"failed\""), "{}", body);
+}
+
+#[test]
+fn reports_timeouts() {
+    let server = Server::start(&["--timeout", "1ms"]);
+    let input = std::fs::read_to_string("data/day11.txt").unwrap();
+    let (status, body) = server.request("POST", "/solve/11/2", &input);
+    assert_eq!(status, 504);
+    assert!(body.contains("\"status\":\"timed_out\""), "{}", body);
+}